@@ -3,7 +3,7 @@ use floem::{
     reactive::create_rw_signal,
     style::{CursorStyle, Style},
     view::View,
-    views::{text_input, Decorators},
+    views::{scroll, text_input, Decorators},
     AppContext,
 };
 
@@ -12,6 +12,8 @@ use crate::form::{form, form_item};
 pub fn text_input_view() -> impl View {
     let cx = AppContext::get_current();
     let text = create_rw_signal(cx.scope, "".to_string());
+    let password = create_rw_signal(cx.scope, "".to_string());
+    let description = create_rw_signal(cx.scope, "".to_string());
 
     form(move || {
         (
@@ -51,6 +53,21 @@ pub fn text_input_view() -> impl View {
                     .keyboard_navigatable()
                     .disabled(|| true)
             }),
+            form_item("Password Input:".to_string(), 120.0, move || {
+                text_input(password)
+                    .password()
+                    .max_length(32)
+                    .placeholder("Password")
+                    .style(|| Style::BASE.border(1.0).height_px(32.0))
+            }),
+            form_item("Multi-line Input:".to_string(), 120.0, move || {
+                scroll(move || {
+                    text_input(description)
+                        .multi_line()
+                        .style(|| Style::BASE.width_px(240.0).padding_px(5.0))
+                })
+                .style(|| Style::BASE.border(1.0).height_px(80.0))
+            }),
         )
     })
 }
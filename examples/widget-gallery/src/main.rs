@@ -6,6 +6,9 @@ pub mod labels;
 pub mod lists;
 pub mod rich_text;
 
+#[cfg(test)]
+mod snapshots;
+
 use floem::{
     event::{Event, EventListener},
     glazier::keyboard_types::Key,
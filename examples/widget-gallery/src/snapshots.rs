@@ -0,0 +1,71 @@
+//! The scenes here draw no text, so the reference images don't depend on the fonts
+//! installed on the machine running the tests.
+
+use floem::{
+    peniko::Color,
+    snapshot::Snapshot,
+    style::Style,
+    views::{container, empty, list, scroll, stack, Decorators},
+};
+
+fn snapshot() -> Snapshot {
+    Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots")).tolerance(8)
+}
+
+#[test]
+fn padded_backgrounds() {
+    snapshot()
+        .size((120.0, 80.0))
+        .assert("padded_backgrounds", || {
+            container(|| empty().style(|| Style::BASE.size_px(80.0, 40.0).background(Color::RED)))
+                .style(|| {
+                    Style::BASE
+                        .size_px(100.0, 60.0)
+                        .padding_px(10.0)
+                        .background(Color::BLUE)
+                })
+        });
+}
+
+#[test]
+fn list_with_gaps() {
+    snapshot().size((100.0, 40.0)).assert("list_with_gaps", || {
+        list(
+            || vec![0, 1, 2],
+            |item| *item,
+            |item| {
+                let color = [Color::RED, Color::rgb8(0, 255, 0), Color::BLUE][item];
+                empty().style(move || Style::BASE.size_px(30.0, 20.0).background(color))
+            },
+        )
+        .style(|| Style::BASE.gap_px(5.0))
+    });
+}
+
+#[test]
+fn scroll_bars() {
+    snapshot().size((120.0, 120.0)).assert("scroll_bars", || {
+        scroll(|| {
+            empty().style(|| {
+                Style::BASE
+                    .size_px(300.0, 300.0)
+                    .background(Color::rgb8(0, 0, 255))
+            })
+        })
+        .style(|| Style::BASE.size_px(100.0, 100.0))
+    });
+}
+
+#[test]
+fn translated_view() {
+    snapshot().size((60.0, 40.0)).assert("translated_view", || {
+        stack(|| {
+            (empty().style(|| {
+                Style::BASE
+                    .size_px(20.0, 20.0)
+                    .translate(30.0, 10.0)
+                    .background(Color::RED)
+            }),)
+        })
+    });
+}
@@ -0,0 +1,201 @@
+use std::f64::consts::TAU;
+
+use peniko::{
+    kurbo::{Point, Rect, Vec2},
+    Color, ColorStop, Extend, Gradient, GradientKind,
+};
+
+/// Renders the gradient over `bounds` into an RGBA image of `width` by `height` pixels,
+/// with straight alpha, for backends that can't draw every kind of gradient
+pub fn render_gradient(gradient: &Gradient, bounds: Rect, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for row in 0..height {
+        for col in 0..width {
+            let point = Point::new(
+                bounds.x0 + (col as f64 + 0.5) / width as f64 * bounds.width(),
+                bounds.y0 + (row as f64 + 0.5) / height as f64 * bounds.height(),
+            );
+            let color = gradient_offset(&gradient.kind, point)
+                .map(|t| sample_stops(&gradient.stops, extend(gradient.extend, t)))
+                .unwrap_or(Color::TRANSPARENT);
+            pixels.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+    pixels
+}
+
+/// Where `point` is along the gradient, 0 at its start and 1 at its end.
+/// Points a radial gradient doesn't reach have none.
+fn gradient_offset(kind: &GradientKind, point: Point) -> Option<f64> {
+    match *kind {
+        GradientKind::Linear { start, end } => {
+            let direction = end - start;
+            let length = direction.hypot2();
+            if length == 0.0 {
+                return Some(0.0);
+            }
+            Some((point - start).dot(direction) / length)
+        }
+        GradientKind::Radial {
+            start_center,
+            start_radius,
+            end_center,
+            end_radius,
+        } => radial_offset(
+            point - start_center,
+            end_center - start_center,
+            start_radius as f64,
+            end_radius as f64,
+        ),
+        GradientKind::Sweep {
+            center,
+            start_angle,
+            end_angle,
+        } => {
+            let (start_angle, end_angle) = (start_angle as f64, end_angle as f64);
+            if start_angle == end_angle {
+                return Some(0.0);
+            }
+            let angle = (point - center).atan2().rem_euclid(TAU);
+            Some((angle - start_angle) / (end_angle - start_angle))
+        }
+    }
+}
+
+/// The largest `t` whose circle, moving from the start circle to the end one,
+/// passes through `point`, which is relative to the start center
+fn radial_offset(point: Vec2, centers: Vec2, start_radius: f64, end_radius: f64) -> Option<f64> {
+    let radius_delta = end_radius - start_radius;
+    let a = centers.hypot2() - radius_delta * radius_delta;
+    let b = point.dot(centers) + start_radius * radius_delta;
+    let c = point.hypot2() - start_radius * start_radius;
+    let radius_at = |t: f64| start_radius + t * radius_delta;
+
+    if a.abs() < 1e-9 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = c / (2.0 * b);
+        return (radius_at(t) >= 0.0).then_some(t);
+    }
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (t0, t1) = ((b + root) / a, (b - root) / a);
+    let (first, second) = if t0 > t1 { (t0, t1) } else { (t1, t0) };
+    if radius_at(first) >= 0.0 {
+        Some(first)
+    } else if radius_at(second) >= 0.0 {
+        Some(second)
+    } else {
+        None
+    }
+}
+
+fn extend(extend: Extend, t: f64) -> f64 {
+    match extend {
+        Extend::Pad => t.clamp(0.0, 1.0),
+        Extend::Repeat => t.rem_euclid(1.0),
+        Extend::Reflect => {
+            let t = t.rem_euclid(2.0);
+            if t > 1.0 {
+                2.0 - t
+            } else {
+                t
+            }
+        }
+    }
+}
+
+/// The color at `t` between the stops, which are sorted by their offsets
+fn sample_stops(stops: &[ColorStop], t: f64) -> Color {
+    let t = t as f32;
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Color::TRANSPARENT,
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+    for pair in stops.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        if t <= to.offset {
+            let span = to.offset - from.offset;
+            if span <= 0.0 {
+                return to.color;
+            }
+            return lerp_color(from.color, to.color, (t - from.offset) / span);
+        }
+    }
+    last.color
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let lerp = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+    Color::rgba8(
+        lerp(from.r, to.r),
+        lerp(from.g, to.g),
+        lerp(from.b, to.b),
+        lerp(from.a, to.a),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use peniko::{
+        kurbo::{Point, Vec2},
+        Color, ColorStop, Extend, GradientKind,
+    };
+
+    use super::{extend, gradient_offset, radial_offset, sample_stops};
+
+    #[test]
+    fn samples_between_multiple_stops() {
+        let stops = [
+            ColorStop {
+                offset: 0.0,
+                color: Color::rgb8(0, 0, 0),
+            },
+            ColorStop {
+                offset: 0.5,
+                color: Color::rgb8(200, 0, 0),
+            },
+            ColorStop {
+                offset: 1.0,
+                color: Color::rgb8(200, 100, 0),
+            },
+        ];
+        assert_eq!(sample_stops(&stops, 0.25), Color::rgb8(100, 0, 0));
+        assert_eq!(sample_stops(&stops, 0.75), Color::rgb8(200, 50, 0));
+        assert_eq!(sample_stops(&stops, 2.0), Color::rgb8(200, 100, 0));
+    }
+
+    #[test]
+    fn extend_modes() {
+        assert_eq!(extend(Extend::Pad, 1.5), 1.0);
+        assert_eq!(extend(Extend::Repeat, 1.25), 0.25);
+        assert_eq!(extend(Extend::Reflect, 1.25), 0.75);
+    }
+
+    #[test]
+    fn radial_offset_of_concentric_circles() {
+        // the circles grow from a radius of 10 to 20 around the same center
+        let t = radial_offset(Vec2::new(15.0, 0.0), Vec2::ZERO, 10.0, 20.0).unwrap();
+        assert!((t - 0.5).abs() < 1e-9);
+        let t = radial_offset(Vec2::new(0.0, 5.0), Vec2::ZERO, 0.0, 10.0).unwrap();
+        assert!((t - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sweep_offset_goes_around_the_center() {
+        let kind = GradientKind::Sweep {
+            center: Point::new(10.0, 10.0),
+            start_angle: 0.0,
+            end_angle: std::f32::consts::TAU,
+        };
+        let t = gradient_offset(&kind, Point::new(10.0, 20.0)).unwrap();
+        assert!((t - 0.25).abs() < 1e-6);
+    }
+}
@@ -1,17 +1,69 @@
+mod gradient;
+mod shadow;
+
 pub use cosmic_text;
 use cosmic_text::TextLayout;
+pub use gradient::render_gradient;
 use peniko::{
-    kurbo::{Affine, Point, Rect, Shape},
-    BrushRef,
+    kurbo::{Affine, Point, Rect, RoundedRectRadii, Shape},
+    BrushRef, Color, Stroke,
 };
 pub use resvg::tiny_skia;
 pub use resvg::usvg;
+pub use shadow::{box_shadow_pixmap, shadow_extent};
 
 pub struct Svg<'a> {
     pub tree: &'a usvg::Tree,
     pub hash: &'a [u8],
 }
 
+/// A decoded raster image
+pub struct Img<'a> {
+    /// The pixels in RGBA, row by row, with straight alpha
+    pub data: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+    /// Identifies the pixels, so renderers can cache what they make of them
+    pub hash: &'a [u8],
+}
+
+impl Img<'_> {
+    /// Scales the image to `width` by `height` pixels
+    pub fn to_pixmap(&self, width: u32, height: u32) -> Option<tiny_skia::Pixmap> {
+        let mut source = tiny_skia::Pixmap::new(self.width, self.height)?;
+        for (pixel, rgba) in source
+            .data_mut()
+            .chunks_exact_mut(4)
+            .zip(self.data.chunks_exact(4))
+        {
+            let a = rgba[3] as u16;
+            let mul = |c: u8| (c as u16 * a / 255) as u8;
+            pixel.copy_from_slice(&[mul(rgba[0]), mul(rgba[1]), mul(rgba[2]), rgba[3]]);
+        }
+        if source.width() == width && source.height() == height {
+            return Some(source);
+        }
+
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+        let paint = tiny_skia::PixmapPaint {
+            quality: tiny_skia::FilterQuality::Bilinear,
+            ..Default::default()
+        };
+        pixmap.draw_pixmap(
+            0,
+            0,
+            source.as_ref(),
+            &paint,
+            tiny_skia::Transform::from_scale(
+                width as f32 / self.width as f32,
+                height as f32 / self.height as f32,
+            ),
+            None,
+        );
+        Some(pixmap)
+    }
+}
+
 pub trait Renderer {
     fn begin(&mut self);
 
@@ -19,13 +71,26 @@ pub trait Renderer {
 
     fn set_z_index(&mut self, z_index: i32);
 
-    /// Clip to a [`Shape`].
-    fn clip(&mut self, shape: &impl Shape);
+    /// Start a group that's composited with `opacity` once it's popped with
+    /// [`Renderer::pop_layer`]. Groups nest, their opacities multiply.
+    fn push_layer(&mut self, opacity: f32);
 
-    fn clear_clip(&mut self);
+    /// Composite the group started by the last [`Renderer::push_layer`].
+    fn pop_layer(&mut self);
 
-    /// Stroke a [`Shape`], using the default [`StrokeStyle`].
-    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, width: f64);
+    /// Clip everything drawn to a [`Shape`], intersected with the clips pushed
+    /// before it, until the matching [`Renderer::pop_clip`].
+    fn push_clip(&mut self, shape: &impl Shape);
+
+    /// Draw without clipping, whatever was pushed before, until the matching
+    /// [`Renderer::pop_clip`].
+    fn push_unclipped(&mut self);
+
+    /// Go back to the clip from before the last push.
+    fn pop_clip(&mut self);
+
+    /// Stroke a [`Shape`] with the width, dashes, caps and joins of `stroke`.
+    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, stroke: &Stroke);
 
     /// Fill a [`Shape`], using the [non-zero fill rule].
     ///
@@ -41,5 +106,19 @@ pub trait Renderer {
 
     fn draw_svg<'b>(&mut self, svg: Svg<'b>, rect: Rect, brush: Option<impl Into<BrushRef<'b>>>);
 
+    /// Draw an [`Img`], stretched to fill `rect`.
+    fn draw_img(&mut self, img: Img<'_>, rect: Rect);
+
+    /// Draw the shadow of `rect` with corners of `radii`, blurred by `blur_radius`.
+    ///
+    /// The shadow reaches [`shadow_extent`] past `rect` on every side.
+    fn draw_box_shadow(
+        &mut self,
+        rect: Rect,
+        radii: RoundedRectRadii,
+        color: Color,
+        blur_radius: f64,
+    );
+
     fn finish(&mut self);
 }
@@ -0,0 +1,205 @@
+use peniko::{kurbo::RoundedRectRadii, Color};
+use resvg::tiny_skia::Pixmap;
+
+/// How far a shadow blurred by `blur_radius` reaches past its shape
+pub fn shadow_extent(blur_radius: f64) -> f64 {
+    blur_radius.max(0.0).ceil()
+}
+
+/// Renders the shadow of a rounded rect of `width` by `height` pixels, with
+/// corners of `radii`, blurred by `blur_radius`. The image is larger than the
+/// rect by [`shadow_extent`] on every side, so the blur has room to fade out.
+pub fn box_shadow_pixmap(
+    width: f64,
+    height: f64,
+    radii: RoundedRectRadii,
+    color: Color,
+    blur_radius: f64,
+) -> Option<Pixmap> {
+    let extent = shadow_extent(blur_radius);
+    let pixmap_width = (width + extent * 2.0).ceil().max(1.0) as u32;
+    let pixmap_height = (height + extent * 2.0).ceil().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(pixmap_width, pixmap_height)?;
+
+    let mut coverage = rounded_rect_coverage(
+        pixmap_width as usize,
+        pixmap_height as usize,
+        extent,
+        width,
+        height,
+        radii,
+    );
+    // the blur radius is twice the standard deviation, the same as in CSS
+    let sigma = blur_radius / 2.0;
+    if sigma > 0.0 {
+        gaussian_blur(
+            &mut coverage,
+            pixmap_width as usize,
+            pixmap_height as usize,
+            sigma,
+        );
+    }
+
+    for (pixel, coverage) in pixmap.data_mut().chunks_exact_mut(4).zip(coverage) {
+        let a = color.a as f64 / 255.0 * coverage;
+        let mul = |c: u8| (c as f64 * a).round() as u8;
+        pixel.copy_from_slice(&[
+            mul(color.r),
+            mul(color.g),
+            mul(color.b),
+            (a * 255.0).round() as u8,
+        ]);
+    }
+    Some(pixmap)
+}
+
+/// How much of each pixel the rounded rect at (`offset`, `offset`) covers
+fn rounded_rect_coverage(
+    pixmap_width: usize,
+    pixmap_height: usize,
+    offset: f64,
+    width: f64,
+    height: f64,
+    radii: RoundedRectRadii,
+) -> Vec<f64> {
+    let half_width = width / 2.0;
+    let half_height = height / 2.0;
+    let clamp = |radius: f64| radius.clamp(0.0, half_width.min(half_height));
+    let center_x = offset + half_width;
+    let center_y = offset + half_height;
+
+    let mut coverage = Vec::with_capacity(pixmap_width * pixmap_height);
+    for row in 0..pixmap_height {
+        for col in 0..pixmap_width {
+            let x = col as f64 + 0.5 - center_x;
+            let y = row as f64 + 0.5 - center_y;
+            // the radius of the corner in the quarter of the pixel
+            let radius = clamp(match (x < 0.0, y < 0.0) {
+                (true, true) => radii.top_left,
+                (false, true) => radii.top_right,
+                (false, false) => radii.bottom_right,
+                (true, false) => radii.bottom_left,
+            });
+            // the signed distance from the center of the pixel to the rounded rect
+            let x = x.abs() - (half_width - radius);
+            let y = y.abs() - (half_height - radius);
+            let outside = x.max(0.0).hypot(y.max(0.0));
+            let inside = x.max(y).min(0.0);
+            let distance = outside + inside - radius;
+            coverage.push((0.5 - distance).clamp(0.0, 1.0));
+        }
+    }
+    coverage
+}
+
+/// Approximates a gaussian blur with three box blurs in each direction
+fn gaussian_blur(values: &mut [f64], width: usize, height: usize, sigma: f64) {
+    let mut scratch = vec![0.0; values.len()];
+    for radius in box_radii(sigma) {
+        for row in 0..height {
+            let start = row * width;
+            box_blur(values, &mut scratch, start, 1, width, radius);
+        }
+        for col in 0..width {
+            box_blur(values, &mut scratch, col, width, height, radius);
+        }
+    }
+}
+
+/// The radii of the three box blurs that add up to a gaussian blur of `sigma`
+fn box_radii(sigma: f64) -> [usize; 3] {
+    let ideal = (12.0 * sigma * sigma / 3.0 + 1.0).sqrt();
+    let mut lower = ideal.floor() as usize;
+    if lower % 2 == 0 {
+        lower = lower.saturating_sub(1);
+    }
+    let lower = lower.max(1);
+    let upper = lower + 2;
+    let lower_f = lower as f64;
+    let lower_count = ((12.0 * sigma * sigma - 3.0 * lower_f * lower_f - 12.0 * lower_f - 9.0)
+        / (-4.0 * lower_f - 4.0))
+        .round();
+    let mut radii = [0; 3];
+    for (i, radius) in radii.iter_mut().enumerate() {
+        let size = if (i as f64) < lower_count {
+            lower
+        } else {
+            upper
+        };
+        *radius = size / 2;
+    }
+    radii
+}
+
+/// Blurs the `len` values from `start`, `stride` apart, with a box of `radius`
+/// on each side. Values past the edges count as zero.
+fn box_blur(
+    values: &mut [f64],
+    scratch: &mut [f64],
+    start: usize,
+    stride: usize,
+    len: usize,
+    radius: usize,
+) {
+    if radius == 0 {
+        return;
+    }
+    let at = |i: usize| start + i * stride;
+    let size = (radius * 2 + 1) as f64;
+    let mut sum: f64 = (0..radius.min(len)).map(|i| values[at(i)]).sum();
+    for i in 0..len {
+        if i + radius < len {
+            sum += values[at(i + radius)];
+        }
+        scratch[i] = sum / size;
+        if i >= radius {
+            sum -= values[at(i - radius)];
+        }
+    }
+    for (i, value) in scratch.iter().take(len).enumerate() {
+        values[at(i)] = *value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use peniko::{kurbo::RoundedRectRadii, Color};
+
+    use super::{box_shadow_pixmap, shadow_extent};
+
+    fn alpha(pixmap: &resvg::tiny_skia::Pixmap, x: u32, y: u32) -> u8 {
+        pixmap.pixel(x, y).unwrap().alpha()
+    }
+
+    #[test]
+    fn sharp_shadow_covers_the_rect() {
+        let pixmap = box_shadow_pixmap(10.0, 10.0, 0.0.into(), Color::BLACK, 0.0).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (10, 10));
+        assert_eq!(alpha(&pixmap, 0, 0), 255);
+        assert_eq!(alpha(&pixmap, 9, 9), 255);
+    }
+
+    #[test]
+    fn blurred_shadow_fades_out() {
+        let blur = 8.0;
+        let extent = shadow_extent(blur) as u32;
+        let pixmap = box_shadow_pixmap(40.0, 40.0, 4.0.into(), Color::BLACK, blur).unwrap();
+        assert_eq!(pixmap.width(), 40 + extent * 2);
+
+        let center = alpha(&pixmap, pixmap.width() / 2, pixmap.height() / 2);
+        let edge = alpha(&pixmap, extent, pixmap.height() / 2);
+        let outside = alpha(&pixmap, 0, pixmap.height() / 2);
+        assert_eq!(center, 255);
+        assert!(edge > outside && edge < center);
+        assert!(outside < 16);
+    }
+
+    #[test]
+    fn corners_have_their_own_radii() {
+        let radii = RoundedRectRadii::new(0.0, 10.0, 0.0, 0.0);
+        let pixmap = box_shadow_pixmap(20.0, 20.0, radii, Color::BLACK, 0.0).unwrap();
+        assert_eq!(alpha(&pixmap, 0, 0), 255);
+        assert_eq!(alpha(&pixmap, 19, 0), 0);
+        assert_eq!(alpha(&pixmap, 19, 19), 255);
+    }
+}
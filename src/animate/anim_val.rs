@@ -6,6 +6,18 @@ pub enum AnimValue {
     Color(Color),
 }
 
+impl From<f64> for AnimValue {
+    fn from(value: f64) -> Self {
+        AnimValue::Float(value)
+    }
+}
+
+impl From<Color> for AnimValue {
+    fn from(color: Color) -> Self {
+        AnimValue::Color(color)
+    }
+}
+
 impl AnimValue {
     pub fn get_f32(self) -> f32 {
         match self {
@@ -27,4 +39,37 @@ impl AnimValue {
             AnimValue::Float(_) => panic!(),
         }
     }
+
+    pub(crate) fn is_color(&self) -> bool {
+        matches!(self, AnimValue::Color(_))
+    }
+
+    /// The value `time` of the way to `to`, where values of different kinds jump at the end
+    pub(crate) fn interpolate(&self, to: &AnimValue, time: f64) -> AnimValue {
+        match (self, to) {
+            (AnimValue::Float(from), AnimValue::Float(to)) => {
+                AnimValue::Float(from + (to - from) * time)
+            }
+            (AnimValue::Color(from), AnimValue::Color(to)) => {
+                let lerp = |from: u8, to: u8| {
+                    (from as f64 + (to as f64 - from as f64) * time)
+                        .round()
+                        .clamp(0.0, 255.0) as u8
+                };
+                AnimValue::Color(Color::rgba8(
+                    lerp(from.r, to.r),
+                    lerp(from.g, to.g),
+                    lerp(from.b, to.b),
+                    lerp(from.a, to.a),
+                ))
+            }
+            _ => {
+                if time < 1.0 {
+                    self.clone()
+                } else {
+                    to.clone()
+                }
+            }
+        }
+    }
 }
@@ -1,10 +1,10 @@
 use super::{
-    anim_val::AnimValue, AnimId, AnimPropKind, AnimState, AnimStateKind, AnimatedProp, Easing,
-    EasingFn, EasingMode,
+    anim_val::AnimValue, keyframe::sample_keyframes, AnimId, AnimPropKind, AnimState,
+    AnimStateKind, AnimatedProp, Easing, EasingFn, EasingMode, KeyFrame,
 };
-use std::{borrow::BorrowMut, collections::HashMap, time::Duration, time::Instant};
+use std::{borrow::BorrowMut, collections::HashMap, fmt, rc::Rc, time::Duration, time::Instant};
 
-use leptos_reactive::create_effect;
+use leptos_reactive::{create_effect, SignalSet, WriteSignal};
 use vello::peniko::Color;
 
 use crate::AppContext;
@@ -17,9 +17,35 @@ pub struct Animation {
     pub(crate) auto_reverse: bool,
     pub(crate) skip: Option<Duration>,
     pub(crate) duration: Duration,
+    /// How long the animation waits before its first pass
+    pub(crate) delay: Duration,
     pub(crate) repeat_mode: RepeatMode,
     pub(crate) repeat_count: usize,
     pub(crate) animated_props: HashMap<AnimPropKind, AnimatedProp>,
+    pub(crate) keyframes: HashMap<AnimPropKind, Vec<KeyFrame>>,
+    pub(crate) listeners: AnimListeners,
+}
+
+/// What's told about the animation going through its passes
+#[derive(Clone, Default)]
+pub(crate) struct AnimListeners {
+    pub(crate) on_start: Option<Rc<dyn Fn()>>,
+    pub(crate) on_repeat: Option<Rc<dyn Fn()>>,
+    pub(crate) on_complete: Option<Rc<dyn Fn()>>,
+    pub(crate) progress: Option<WriteSignal<f64>>,
+    /// The progress last written to the signal
+    pub(crate) last_progress: Option<f64>,
+}
+
+impl fmt::Debug for AnimListeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnimListeners")
+            .field("on_start", &self.on_start.is_some())
+            .field("on_repeat", &self.on_repeat.is_some())
+            .field("on_complete", &self.on_complete.is_some())
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
 }
 
 pub(crate) fn assert_valid_time(time: f64) {
@@ -47,9 +73,12 @@ pub fn animation() -> Animation {
         auto_reverse: false,
         skip: None,
         duration: Duration::from_secs(1),
+        delay: Duration::ZERO,
         repeat_mode: RepeatMode::Times(1),
         repeat_count: 0,
         animated_props: HashMap::new(),
+        keyframes: HashMap::new(),
+        listeners: AnimListeners::default(),
     }
 }
 
@@ -84,6 +113,77 @@ impl Animation {
         self
     }
 
+    /// Waits for `delay` before the first pass of the animation starts.
+    /// Repeated passes start right away.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Animates the property through the values of the keyframes, instead of
+    /// from its current value to a new one. Each keyframe can have its own
+    /// easing to the next keyframe, otherwise the easing of the animation is used.
+    ///
+    /// Keyframes with a value of the wrong kind for the property, like a number
+    /// for a color, are left out, and without any keyframes the property isn't animated.
+    ///
+    /// ```rust,ignore
+    /// animation()
+    ///     .keyframes(
+    ///         AnimPropKind::Opacity,
+    ///         [
+    ///             KeyFrame::new(0.0, 1.0),
+    ///             KeyFrame::new(0.5, 0.2).easing(Easing::new(EasingFn::Cubic, EasingMode::Out)),
+    ///             KeyFrame::new(1.0, 1.0),
+    ///         ],
+    ///     )
+    ///     .repeat(true)
+    /// ```
+    pub fn keyframes(
+        mut self,
+        kind: AnimPropKind,
+        frames: impl IntoIterator<Item = KeyFrame>,
+    ) -> Self {
+        let mut frames: Vec<KeyFrame> = frames
+            .into_iter()
+            .filter(|frame| frame.value.is_color() == kind.is_color())
+            .collect();
+        if frames.is_empty() {
+            self.keyframes.remove(&kind);
+            return self;
+        }
+        frames.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        self.keyframes.insert(kind, frames);
+        self
+    }
+
+    /// Runs `action` when the animation starts, which is also when it's restarted
+    /// because one of its values changed
+    pub fn on_start(mut self, action: impl Fn() + 'static) -> Self {
+        self.listeners.on_start = Some(Rc::new(action));
+        self
+    }
+
+    /// Runs `action` when the animation starts another pass
+    pub fn on_repeat(mut self, action: impl Fn() + 'static) -> Self {
+        self.listeners.on_repeat = Some(Rc::new(action));
+        self
+    }
+
+    /// Runs `action` when the last pass of the animation ends. Animations that
+    /// repeat forever never complete.
+    pub fn on_complete(mut self, action: impl Fn() + 'static) -> Self {
+        self.listeners.on_complete = Some(Rc::new(action));
+        self
+    }
+
+    /// Keeps `progress` set to how far along its current pass the animation is,
+    /// from 0 to 1, not counting the delay
+    pub fn progress(mut self, progress: WriteSignal<f64>) -> Self {
+        self.listeners.progress = Some(progress);
+        self
+    }
+
     pub fn is_idle(&self) -> bool {
         matches!(self.state_kind(), AnimStateKind::Idle)
     }
@@ -100,10 +200,58 @@ impl Animation {
         self.auto_reverse
     }
 
-    // pub fn scale(self, scale: f64) -> Self {
-    //     todo!()
-    // }
+    /// Animates the scale in both directions, around the transform origin
+    pub fn scale(self, scale_fn: impl Fn() -> f64 + 'static) -> Self {
+        let cx = AppContext::get_current();
+        create_effect(cx.scope, move |_| {
+            let scale = scale_fn();
+
+            self.id
+                .update_prop(AnimPropKind::Scale, AnimValue::Float(scale));
+        });
+
+        self
+    }
+
+    pub fn translate_x(self, translate_x_fn: impl Fn() -> f64 + 'static) -> Self {
+        let cx = AppContext::get_current();
+        create_effect(cx.scope, move |_| {
+            let translate_x = translate_x_fn();
+
+            self.id
+                .update_prop(AnimPropKind::TranslateX, AnimValue::Float(translate_x));
+        });
+
+        self
+    }
+
+    pub fn translate_y(self, translate_y_fn: impl Fn() -> f64 + 'static) -> Self {
+        let cx = AppContext::get_current();
+        create_effect(cx.scope, move |_| {
+            let translate_y = translate_y_fn();
+
+            self.id
+                .update_prop(AnimPropKind::TranslateY, AnimValue::Float(translate_y));
+        });
+
+        self
+    }
+
+    /// Animates the rotation, in radians
+    pub fn rotate(self, rotate_fn: impl Fn() -> f64 + 'static) -> Self {
+        let cx = AppContext::get_current();
+        create_effect(cx.scope, move |_| {
+            let rotate = rotate_fn();
+
+            self.id
+                .update_prop(AnimPropKind::Rotate, AnimValue::Float(rotate));
+        });
 
+        self
+    }
+
+    /// Animates all four corner radii to the same radius. They all start from
+    /// the top-left radius, so corners with other radii jump to it first.
     pub fn border_radius(self, border_radius_fn: impl Fn() -> f64 + 'static) -> Self {
         let cx = AppContext::get_current();
         create_effect(cx.scope, move |_| {
@@ -128,6 +276,8 @@ impl Animation {
         self
     }
 
+    /// Animates the colors of all four sides to the same color. They all start
+    /// from the top color, so sides with other colors jump to it first.
     pub fn border_color(self, bord_color_fn: impl Fn() -> Color + 'static) -> Self {
         let cx = AppContext::get_current();
         create_effect(cx.scope, move |_| {
@@ -176,6 +326,18 @@ impl Animation {
         self
     }
 
+    pub fn opacity(self, opacity_fn: impl Fn() -> f64 + 'static) -> Self {
+        let cx = AppContext::get_current();
+        create_effect(cx.scope, move |_| {
+            let opacity = opacity_fn();
+
+            self.id
+                .update_prop(AnimPropKind::Opacity, AnimValue::Float(opacity));
+        });
+
+        self
+    }
+
     pub fn auto_reverse(mut self, auto_rev: bool) -> Self {
         self.auto_reverse = auto_rev;
         self
@@ -220,10 +382,18 @@ impl Animation {
     }
 
     pub fn begin(&mut self) {
+        self.begin_at(Instant::now());
+    }
+
+    /// Starts the animation as if it were `now`
+    pub(crate) fn begin_at(&mut self, now: Instant) {
         self.repeat_count = 0;
         self.state = AnimState::PassInProgress {
-            started_on: Instant::now(),
+            started_on: now,
             elapsed: Duration::ZERO,
+        };
+        if let Some(on_start) = self.listeners.on_start.clone() {
+            on_start();
         }
     }
 
@@ -253,13 +423,18 @@ impl Animation {
     }
 
     pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed_at(Instant::now())
+    }
+
+    /// The time the animation has run for as of `now`
+    pub(crate) fn elapsed_at(&self, now: Instant) -> Option<Duration> {
         match &self.state {
             AnimState::Idle => None,
             AnimState::PassInProgress {
                 started_on,
                 elapsed,
             } => {
-                let duration = Instant::now() - started_on.clone();
+                let duration = now.saturating_duration_since(*started_on);
                 Some(*elapsed + duration)
             }
             AnimState::PassFinished { elapsed } => Some(elapsed.clone()),
@@ -268,27 +443,35 @@ impl Animation {
     }
 
     pub fn advance(&mut self) {
+        self.advance_at(Instant::now());
+    }
+
+    /// Moves the animation along to where it is at `now`
+    pub(crate) fn advance_at(&mut self, now: Instant) {
         match &mut self.state {
             AnimState::Idle => {
-                self.begin();
+                self.begin_at(now);
             }
             AnimState::PassInProgress {
                 started_on,
                 mut elapsed,
             } => {
-                let now = Instant::now();
-                let duration = now - started_on.clone();
+                let duration = now.saturating_duration_since(*started_on);
                 elapsed += duration;
 
-                if elapsed >= self.duration {
+                if elapsed >= self.pass_duration() {
                     self.state = AnimState::PassFinished { elapsed };
                 }
             }
             AnimState::PassFinished { elapsed } => match self.repeat_mode {
                 RepeatMode::LoopForever => {
+                    self.repeat_count += 1;
                     self.state = AnimState::PassInProgress {
-                        started_on: Instant::now(),
+                        started_on: now,
                         elapsed: Duration::ZERO,
+                    };
+                    if let Some(on_repeat) = self.listeners.on_repeat.clone() {
+                        on_repeat();
                     }
                 }
                 RepeatMode::Times(times) => {
@@ -296,21 +479,70 @@ impl Animation {
                     if self.repeat_count >= times {
                         self.state = AnimState::Completed {
                             elapsed: Some(*elapsed),
+                        };
+                        if let Some(on_complete) = self.listeners.on_complete.clone() {
+                            on_complete();
                         }
                     } else {
                         self.state = AnimState::PassInProgress {
-                            started_on: Instant::now(),
+                            started_on: now,
                             elapsed: Duration::ZERO,
+                        };
+                        if let Some(on_repeat) = self.listeners.on_repeat.clone() {
+                            on_repeat();
                         }
                     }
                 }
             },
             AnimState::Completed { .. } => {}
         }
+        self.update_progress(now);
+    }
+
+    /// How far along its current pass the animation is as of `now`, from 0 to 1
+    pub(crate) fn pass_progress(&self, now: Instant) -> f64 {
+        match self.state {
+            AnimState::Idle => 0.0,
+            AnimState::PassInProgress { .. } => {
+                let mut elapsed = self.elapsed_at(now).unwrap_or(Duration::ZERO);
+                if self.repeat_count == 0 {
+                    elapsed = elapsed.saturating_sub(self.delay);
+                }
+                if self.duration == Duration::ZERO {
+                    return 1.0;
+                }
+                (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+            }
+            AnimState::PassFinished { .. } | AnimState::Completed { .. } => 1.0,
+        }
+    }
+
+    fn update_progress(&mut self, now: Instant) {
+        if let Some(progress) = self.listeners.progress {
+            let value = self.pass_progress(now);
+            if self.listeners.last_progress != Some(value) {
+                self.listeners.last_progress = Some(value);
+                progress.set(value);
+            }
+        }
     }
 
-    pub(crate) fn props(&self) -> &HashMap<AnimPropKind, AnimatedProp> {
-        &self.animated_props
+    /// The properties that are animated, from their values or keyframes
+    pub(crate) fn prop_kinds(&self) -> impl Iterator<Item = &AnimPropKind> {
+        self.animated_props.keys().chain(
+            self.keyframes
+                .keys()
+                .filter(|kind| !self.animated_props.contains_key(kind)),
+        )
+    }
+
+    /// How long the current pass takes, with the delay before the first one
+    fn pass_duration(&self) -> Duration {
+        if self.repeat_count == 0 {
+            self.delay + self.duration
+        } else {
+            self.duration
+        }
     }
 
     pub(crate) fn props_mut(&mut self) -> &mut HashMap<AnimPropKind, AnimatedProp> {
@@ -319,12 +551,29 @@ impl Animation {
 
     pub(crate) fn animate_prop(&self, elapsed: Duration, prop_kind: &AnimPropKind) -> AnimValue {
         let mut elapsed = elapsed;
-        let prop = self.animated_props.get(&prop_kind).unwrap();
-
+        if self.repeat_count == 0 {
+            elapsed = elapsed.saturating_sub(self.delay);
+        }
         if let Some(skip) = self.skip {
             elapsed += skip;
         }
 
+        if let Some(frames) = self.keyframes.get(prop_kind) {
+            let time = if self.duration == Duration::ZERO {
+                0.0
+            } else {
+                (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+            };
+            let time = match (self.auto_reverse, time > 0.5) {
+                (true, true) => 2.0 - time * 2.0,
+                (true, false) => time * 2.0,
+                (false, _) => time,
+            };
+            return sample_keyframes(frames, time, &self.easing);
+        }
+
+        let prop = self.animated_props.get(&prop_kind).unwrap();
+
         if self.duration == Duration::ZERO {
             return prop.from();
         }
@@ -348,3 +597,99 @@ impl Animation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use std::{cell::RefCell, rc::Rc};
+
+    use leptos_reactive::{create_signal, SignalGetUntracked};
+    use vello::peniko::Color;
+
+    use crate::{
+        animate::{AnimPropKind, KeyFrame},
+        test_harness::TestHarness,
+        views::empty,
+    };
+
+    use super::animation;
+
+    #[test]
+    fn keyframes_start_after_the_delay() {
+        let anim = animation()
+            .duration(Duration::from_secs(1))
+            .delay(Duration::from_millis(500))
+            .keyframes(
+                AnimPropKind::Opacity,
+                [
+                    KeyFrame::new(1.0, 0.0),
+                    KeyFrame::new(0.0, 1.0),
+                    KeyFrame::new(0.5, 0.5),
+                ],
+            );
+        let opacity_at =
+            |millis| anim.animate_prop(Duration::from_millis(millis), &AnimPropKind::Opacity);
+
+        assert_eq!(opacity_at(250).get_f64(), 1.0);
+        assert_eq!(opacity_at(500).get_f64(), 1.0);
+        assert_eq!(opacity_at(1000).get_f64(), 0.5);
+        assert_eq!(opacity_at(1250).get_f64(), 0.25);
+        assert_eq!(opacity_at(2000).get_f64(), 0.0);
+        assert_eq!(anim.prop_kinds().count(), 1);
+    }
+
+    #[test]
+    fn keyframes_of_the_wrong_kind_are_left_out() {
+        let anim = animation()
+            .keyframes(
+                AnimPropKind::Background,
+                [
+                    KeyFrame::new(0.0, 1.0),
+                    KeyFrame::new(1.0, Color::rgb8(200, 100, 0)),
+                ],
+            )
+            .keyframes(AnimPropKind::Opacity, [KeyFrame::new(0.0, Color::BLACK)])
+            .keyframes(AnimPropKind::Scale, []);
+
+        let kinds: Vec<&AnimPropKind> = anim.prop_kinds().collect();
+        assert_eq!(kinds, [&AnimPropKind::Background]);
+        assert_eq!(
+            anim.animate_prop(Duration::ZERO, &AnimPropKind::Background)
+                .get_color(),
+            Color::rgb8(200, 100, 0)
+        );
+    }
+
+    #[test]
+    fn listeners_follow_the_passes() {
+        let harness = TestHarness::new(empty, (10.0, 10.0));
+        let (progress, set_progress) = create_signal(harness.scope(), 0.5);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = |name: &'static str| {
+            let events = events.clone();
+            move || events.borrow_mut().push(name)
+        };
+        let mut anim = animation()
+            .duration(Duration::ZERO)
+            .repeat_times(2)
+            .on_start(log("start"))
+            .on_repeat(log("repeat"))
+            .on_complete(log("complete"))
+            .progress(set_progress);
+
+        // idle, to the first pass, which is over right away
+        anim.advance();
+        assert_eq!(progress.get_untracked(), 1.0);
+        anim.advance();
+        // to the second pass
+        anim.advance();
+        anim.advance();
+        assert!(!anim.is_completed());
+        anim.advance();
+        assert!(anim.is_completed());
+        anim.advance();
+
+        assert_eq!(*events.borrow(), ["start", "repeat", "complete"]);
+    }
+}
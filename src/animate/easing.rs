@@ -27,8 +27,8 @@ pub enum EasingFn {
     /// Creates an animation that accelerates and/or decelerates using an exponential formula.
     Exponential,
     /// Creates an animation that accelerates and/or
-    /// decelerates using the formula `f(t) = tp` where p is equal to the Power property.
-    Power,
+    /// decelerates using the formula `f(t) = tp` where p is the power.
+    Power(f64),
     /// Creates an animation that accelerates and/or decelerates using the formula `f(t) = t2`.
     Quadratic,
     /// Creates an animation that accelerates and/or decelerates using the formula `f(t) = t3`.
@@ -39,6 +39,27 @@ pub enum EasingFn {
     Quintic,
     /// Creates an animation that accelerates and/or decelerates using a sine formula.
     Sine,
+    /// Follows a cubic bezier curve from (0, 0) to (1, 1) with the two control
+    /// points, like CSS's `cubic-bezier(x1, y1, x2, y2)`. `x1` and `x2` must be
+    /// between 0 and 1.
+    ///
+    /// The curve is complete on its own, so it's usually used with [`EasingMode::In`].
+    CubicBezier {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    /// Follows a spring with a mass of 1 pulled from 0 to 1, which may overshoot
+    /// and oscillate when it's not damped enough. The progress of the animation
+    /// is the time in seconds of the spring's motion, so the spring should
+    /// settle by 1 for the animation not to jump at its end.
+    ///
+    /// The motion is complete on its own, so it's usually used with [`EasingMode::In`].
+    Spring {
+        stiffness: f64,
+        damping: f64,
+    },
 }
 
 impl Default for EasingMode {
@@ -66,6 +87,98 @@ fn elastic_easing(time: f64) -> f64 {
     }
 }
 
+fn back_easing(time: f64) -> f64 {
+    let c1 = 1.70158;
+    let c3 = c1 + 1.0;
+    c3 * time.powi(3) - c1 * time.powi(2)
+}
+
+fn bounce_easing(time: f64) -> f64 {
+    // the bounces are defined going out, from the first bounce to rest
+    let time = 1.0 - time;
+    let n1 = 7.5625;
+    let d1 = 2.75;
+    let out = if time < 1.0 / d1 {
+        n1 * time * time
+    } else if time < 2.0 / d1 {
+        let time = time - 1.5 / d1;
+        n1 * time * time + 0.75
+    } else if time < 2.5 / d1 {
+        let time = time - 2.25 / d1;
+        n1 * time * time + 0.9375
+    } else {
+        let time = time - 2.625 / d1;
+        n1 * time * time + 0.984375
+    };
+    1.0 - out
+}
+
+fn cubic_bezier_easing(time: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let bezier = |s: f64, p1: f64, p2: f64| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+    };
+    let bezier_slope = |s: f64, p1: f64, p2: f64| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * p1 + 6.0 * inv * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+    };
+
+    // find where on the curve x is the time, first with Newton's method and
+    // then by bisection when the slope is too flat for it
+    let mut s = time;
+    for _ in 0..8 {
+        let error = bezier(s, x1, x2) - time;
+        if error.abs() < 1e-7 {
+            return bezier(s, y1, y2);
+        }
+        let slope = bezier_slope(s, x1, x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    s = time;
+    for _ in 0..64 {
+        let x = bezier(s, x1, x2);
+        if (x - time).abs() < 1e-7 {
+            break;
+        }
+        if x < time {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    bezier(s, y1, y2)
+}
+
+fn spring_easing(time: f64, stiffness: f64, damping: f64) -> f64 {
+    if time == 0.0 {
+        return 0.0;
+    }
+    if (1.0 - time).abs() < f64::EPSILON || stiffness <= 0.0 {
+        return 1.0;
+    }
+    let natural_frequency = stiffness.sqrt();
+    let damping_ratio = damping / (2.0 * natural_frequency);
+    let displacement = if damping_ratio < 1.0 {
+        let decay = damping_ratio * natural_frequency;
+        let frequency = natural_frequency * (1.0 - damping_ratio * damping_ratio).sqrt();
+        (-decay * time).exp()
+            * ((frequency * time).cos() + decay / frequency * (frequency * time).sin())
+    } else if damping_ratio == 1.0 {
+        (-natural_frequency * time).exp() * (1.0 + natural_frequency * time)
+    } else {
+        let root = (damping_ratio * damping_ratio - 1.0).sqrt();
+        let r1 = -natural_frequency * (damping_ratio - root);
+        let r2 = -natural_frequency * (damping_ratio + root);
+        (r2 * (r1 * time).exp() - r1 * (r2 * time).exp()) / (r2 - r1)
+    };
+    1.0 - displacement
+}
+
 impl Easing {
     pub(crate) fn apply_easing_fn(&self, time: f64) -> f64 {
         assert_valid_time(time);
@@ -80,14 +193,16 @@ impl Easing {
                     2.0f64.powf(10.0 * time - 10.0)
                 }
             }
-            EasingFn::Power => todo!(),
+            EasingFn::Power(power) => time.powf(power),
             EasingFn::Quadratic => time.powf(2.0),
             EasingFn::Cubic => time.powf(3.0),
             EasingFn::Quartic => time.powf(4.0),
             EasingFn::Quintic => time.powf(5.0),
             EasingFn::Sine => 1.0 - ((time * PI) / 2.0).cos(),
-            EasingFn::Back => todo!(),
-            EasingFn::Bounce => todo!(),
+            EasingFn::Back => back_easing(time),
+            EasingFn::Bounce => bounce_easing(time),
+            EasingFn::CubicBezier { x1, y1, x2, y2 } => cubic_bezier_easing(time, x1, y1, x2, y2),
+            EasingFn::Spring { stiffness, damping } => spring_easing(time, stiffness, damping),
         }
     }
 
@@ -106,6 +221,10 @@ impl Easing {
         }
     }
 
+    pub fn new(func: EasingFn, mode: EasingMode) -> Easing {
+        Easing { mode, func }
+    }
+
     pub fn default() -> Easing {
         Easing {
             mode: EasingMode::In,
@@ -113,3 +232,103 @@ impl Easing {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Easing, EasingFn, EasingMode};
+
+    fn sample(func: EasingFn, time: f64) -> f64 {
+        Easing {
+            mode: EasingMode::In,
+            func,
+        }
+        .ease(time)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn polynomial_curves() {
+        assert_close(sample(EasingFn::Linear, 0.3), 0.3);
+        assert_close(sample(EasingFn::Quadratic, 0.5), 0.25);
+        assert_close(sample(EasingFn::Cubic, 0.5), 0.125);
+        assert_close(sample(EasingFn::Power(2.5), 0.25), 0.03125);
+        assert_close(sample(EasingFn::Sine, 0.5), 0.292893);
+        assert_close(sample(EasingFn::Circle, 0.5), 0.133975);
+    }
+
+    #[test]
+    fn back_retracts_before_moving() {
+        assert_close(sample(EasingFn::Back, 0.5), -0.087698);
+        assert_close(sample(EasingFn::Back, 1.0), 1.0);
+    }
+
+    #[test]
+    fn bounce_curve() {
+        assert_close(sample(EasingFn::Bounce, 0.0), 0.0);
+        assert_close(sample(EasingFn::Bounce, 0.5), 0.234375);
+        assert_close(sample(EasingFn::Bounce, 1.0), 1.0);
+    }
+
+    #[test]
+    fn cubic_bezier_matches_css() {
+        // CSS's `ease`
+        let ease = EasingFn::CubicBezier {
+            x1: 0.25,
+            y1: 0.1,
+            x2: 0.25,
+            y2: 1.0,
+        };
+        assert_close(sample(ease, 0.5), 0.802403);
+        assert_close(sample(ease, 0.0), 0.0);
+        assert_close(sample(ease, 1.0), 1.0);
+
+        let linear = EasingFn::CubicBezier {
+            x1: 0.0,
+            y1: 0.0,
+            x2: 1.0,
+            y2: 1.0,
+        };
+        assert_close(sample(linear, 0.3), 0.3);
+    }
+
+    #[test]
+    fn spring_settles_and_overshoots() {
+        // critically damped
+        let spring = EasingFn::Spring {
+            stiffness: 100.0,
+            damping: 20.0,
+        };
+        assert_close(sample(spring, 0.5), 1.0 - 6.0 * (-5.0f64).exp());
+        assert_close(sample(spring, 1.0), 1.0);
+
+        let bouncy = EasingFn::Spring {
+            stiffness: 100.0,
+            damping: 5.0,
+        };
+        let peak = (1..100)
+            .map(|i| sample(bouncy, i as f64 / 100.0))
+            .fold(0.0, f64::max);
+        assert!(peak > 1.0);
+    }
+
+    #[test]
+    fn modes_mirror_the_curve() {
+        let out = Easing {
+            mode: EasingMode::Out,
+            func: EasingFn::Quadratic,
+        };
+        assert_close(out.ease(0.5), 0.75);
+        let in_out = Easing {
+            mode: EasingMode::InOut,
+            func: EasingFn::Quadratic,
+        };
+        assert_close(in_out.ease(0.25), 0.125);
+        assert_close(in_out.ease(0.75), 0.875);
+    }
+}
@@ -0,0 +1,111 @@
+use super::{anim_val::AnimValue, Easing};
+
+/// A value a property takes at some point of an animation, see [`Animation::keyframes`](super::Animation::keyframes).
+#[derive(Clone, Debug)]
+pub struct KeyFrame {
+    /// Where the keyframe is in the animation, from 0 at its start to 1 at its end
+    pub(crate) offset: f64,
+    pub(crate) value: AnimValue,
+    /// The easing until the next keyframe, instead of the easing of the animation
+    pub(crate) easing: Option<Easing>,
+}
+
+impl KeyFrame {
+    pub fn new(offset: f64, value: impl Into<AnimValue>) -> Self {
+        Self {
+            offset: offset.clamp(0.0, 1.0),
+            value: value.into(),
+            easing: None,
+        }
+    }
+
+    /// The easing from this keyframe to the next one
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = Some(easing);
+        self
+    }
+}
+
+/// The value at `time` between the keyframes, which are sorted by their offsets.
+/// Before the first keyframe and after the last one, their values are held.
+/// There's at least one keyframe, [`Animation::keyframes`](super::Animation::keyframes)
+/// leaves out properties without any.
+pub(crate) fn sample_keyframes(frames: &[KeyFrame], time: f64, easing: &Easing) -> AnimValue {
+    let (first, last) = (&frames[0], &frames[frames.len() - 1]);
+    if time <= first.offset {
+        return first.value.clone();
+    }
+    for pair in frames.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        if time <= to.offset {
+            let span = to.offset - from.offset;
+            if span <= 0.0 {
+                return to.value.clone();
+            }
+            let time = (time - from.offset) / span;
+            let time = from.easing.as_ref().unwrap_or(easing).ease(time);
+            return from.value.interpolate(&to.value, time);
+        }
+    }
+    last.value.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use vello::peniko::Color;
+
+    use crate::animate::{anim_val::AnimValue, Easing, EasingFn, EasingMode};
+
+    use super::{sample_keyframes, KeyFrame};
+
+    fn float(value: AnimValue) -> f64 {
+        value.get_f64()
+    }
+
+    #[test]
+    fn samples_between_keyframes() {
+        let frames = [
+            KeyFrame::new(0.0, 0.0),
+            KeyFrame::new(0.5, 1.0),
+            KeyFrame::new(1.0, 0.0),
+        ];
+        let linear = Easing::default();
+        assert_eq!(float(sample_keyframes(&frames, 0.25, &linear)), 0.5);
+        assert_eq!(float(sample_keyframes(&frames, 0.5, &linear)), 1.0);
+        assert_eq!(float(sample_keyframes(&frames, 0.75, &linear)), 0.5);
+    }
+
+    #[test]
+    fn keyframe_easing_overrides_the_animation() {
+        let frames = [
+            KeyFrame::new(0.0, 0.0).easing(Easing::new(EasingFn::Quadratic, EasingMode::In)),
+            KeyFrame::new(0.5, 1.0),
+            KeyFrame::new(1.0, 2.0),
+        ];
+        let linear = Easing::default();
+        // eased quadratically up to the second keyframe, and linearly after it
+        assert_eq!(float(sample_keyframes(&frames, 0.25, &linear)), 0.25);
+        assert_eq!(float(sample_keyframes(&frames, 0.75, &linear)), 1.5);
+    }
+
+    #[test]
+    fn holds_values_outside_the_keyframes() {
+        let frames = [
+            KeyFrame::new(0.25, Color::rgb8(0, 0, 0)),
+            KeyFrame::new(0.75, Color::rgb8(200, 100, 0)),
+        ];
+        let linear = Easing::default();
+        assert_eq!(
+            sample_keyframes(&frames, 0.0, &linear).get_color(),
+            Color::rgb8(0, 0, 0)
+        );
+        assert_eq!(
+            sample_keyframes(&frames, 0.5, &linear).get_color(),
+            Color::rgb8(100, 50, 0)
+        );
+        assert_eq!(
+            sample_keyframes(&frames, 1.0, &linear).get_color(),
+            Color::rgb8(200, 100, 0)
+        );
+    }
+}
@@ -13,5 +13,11 @@ pub use anim_val::*;
 mod easing;
 pub use easing::*;
 
+mod keyframe;
+pub use keyframe::*;
+
 mod prop;
 pub use prop::*;
+
+mod transition;
+pub use transition::*;
@@ -9,9 +9,10 @@ pub enum AnimatedProp {
     Width { from: f64, to: f64, unit: SizeUnit },
     Height { from: f64, to: f64, unit: SizeUnit },
     Scale { from: f64, to: f64 },
-    // Opacity { from: f64, to: f64 },
-    // TranslateX,
-    // TranslateY,
+    Opacity { from: f64, to: f64 },
+    TranslateX { from: f64, to: f64 },
+    TranslateY { from: f64, to: f64 },
+    Rotate { from: f64, to: f64 },
     Background { from: Color, to: Color },
     BorderRadius { from: f64, to: f64 },
     BorderWidth { from: f64, to: f64 },
@@ -25,8 +26,12 @@ impl AnimatedProp {
             AnimatedProp::Width { from, .. }
             | AnimatedProp::Height { from, .. }
             | AnimatedProp::BorderWidth { from, .. }
-            | AnimatedProp::BorderRadius { from, .. } => AnimValue::Float(*from),
-            AnimatedProp::Scale { .. } => todo!(),
+            | AnimatedProp::BorderRadius { from, .. }
+            | AnimatedProp::Opacity { from, .. }
+            | AnimatedProp::Scale { from, .. }
+            | AnimatedProp::TranslateX { from, .. }
+            | AnimatedProp::TranslateY { from, .. }
+            | AnimatedProp::Rotate { from, .. } => AnimValue::Float(*from),
             AnimatedProp::Background { from, .. }
             | AnimatedProp::BorderColor { from, .. }
             | AnimatedProp::Color { from, .. } => AnimValue::Color(*from),
@@ -116,8 +121,13 @@ impl AnimatedProp {
             | AnimatedProp::Color { from, to } => {
                 AnimValue::Color(self.animate_color(*from, *to, time, direction))
             }
-            AnimatedProp::Scale { .. } => todo!(),
-            AnimatedProp::BorderRadius { from, to } | AnimatedProp::BorderWidth { from, to } => {
+            AnimatedProp::BorderRadius { from, to }
+            | AnimatedProp::BorderWidth { from, to }
+            | AnimatedProp::Opacity { from, to }
+            | AnimatedProp::Scale { from, to }
+            | AnimatedProp::TranslateX { from, to }
+            | AnimatedProp::TranslateY { from, to }
+            | AnimatedProp::Rotate { from, to } => {
                 AnimValue::Float(self.animate_float(*from, *to, time, direction))
             }
         }
@@ -127,12 +137,24 @@ impl AnimatedProp {
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
 pub enum AnimPropKind {
     Scale,
-    // TranslateX,
-    // TranslateY,
+    TranslateX,
+    TranslateY,
+    Rotate,
     Width,
     Background,
     Color,
     Height,
     BorderRadius,
     BorderColor,
+    Opacity,
+}
+
+impl AnimPropKind {
+    /// Whether the property is a color, the others are numbers
+    pub(crate) fn is_color(&self) -> bool {
+        matches!(
+            self,
+            AnimPropKind::Background | AnimPropKind::Color | AnimPropKind::BorderColor
+        )
+    }
 }
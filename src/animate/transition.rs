@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use super::{animation, AnimPropKind, Animation, Easing, EasingFn, EasingMode, KeyFrame};
+
+/// How a child of a [`list`](crate::views::list) or [`tab`](crate::views::tab)
+/// appears when it's added, or vanishes when it's removed.
+///
+/// The effects go between the hidden state they describe and the view being
+/// fully opaque, in place and at its normal size, and can be combined:
+/// ```rust,ignore
+/// list(each_fn, key_fn, view_fn)
+///     .enter(Transition::fade().and(Transition::slide(0.0, 20.0)))
+///     .exit(Transition::scale(0.8).duration(Duration::from_millis(100)))
+/// ```
+#[derive(Clone, Debug)]
+pub struct Transition {
+    effects: Vec<TransitionEffect>,
+    duration: Duration,
+    delay: Duration,
+    easing: Easing,
+}
+
+#[derive(Clone, Debug)]
+enum TransitionEffect {
+    /// Fully transparent
+    Fade,
+    /// Moved by the offset
+    Slide { x: f64, y: f64 },
+    /// Scaled by the factor, around the transform origin
+    Scale(f64),
+}
+
+impl Transition {
+    fn new(effect: TransitionEffect) -> Self {
+        Self {
+            effects: vec![effect],
+            duration: Duration::from_millis(200),
+            delay: Duration::ZERO,
+            easing: Easing::new(EasingFn::Cubic, EasingMode::Out),
+        }
+    }
+
+    /// Fades the view in from, or out to, full transparency
+    pub fn fade() -> Self {
+        Self::new(TransitionEffect::Fade)
+    }
+
+    /// Slides the view in from, or out to, `x` and `y` away from its place
+    pub fn slide(x: f64, y: f64) -> Self {
+        Self::new(TransitionEffect::Slide { x, y })
+    }
+
+    /// Scales the view in from, or out to, `scale` times its size
+    pub fn scale(scale: f64) -> Self {
+        Self::new(TransitionEffect::Scale(scale))
+    }
+
+    /// Runs the effects of `other` at the same time, with the timing of this transition
+    pub fn and(mut self, other: Transition) -> Self {
+        self.effects.extend(other.effects);
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn easing_fn(mut self, easing_fn: EasingFn) -> Self {
+        self.easing.func = easing_fn;
+        self
+    }
+
+    pub fn ease_mode(mut self, mode: EasingMode) -> Self {
+        self.easing.mode = mode;
+        self
+    }
+
+    /// The animation from the hidden state to the shown one
+    pub(crate) fn enter_animation(&self) -> Animation {
+        self.animation(true)
+    }
+
+    /// The animation from the shown state to the hidden one
+    pub(crate) fn exit_animation(&self) -> Animation {
+        self.animation(false)
+    }
+
+    fn animation(&self, enter: bool) -> Animation {
+        let mut animation = animation()
+            .duration(self.duration)
+            .delay(self.delay)
+            .easing_fn(self.easing.func)
+            .ease_mode(self.easing.mode);
+        for effect in &self.effects {
+            let props = match *effect {
+                TransitionEffect::Fade => vec![(AnimPropKind::Opacity, 0.0, 1.0)],
+                TransitionEffect::Slide { x, y } => vec![
+                    (AnimPropKind::TranslateX, x, 0.0),
+                    (AnimPropKind::TranslateY, y, 0.0),
+                ],
+                TransitionEffect::Scale(scale) => vec![(AnimPropKind::Scale, scale, 1.0)],
+            };
+            for (kind, hidden, shown) in props {
+                let (from, to) = if enter {
+                    (hidden, shown)
+                } else {
+                    (shown, hidden)
+                };
+                animation =
+                    animation.keyframes(kind, [KeyFrame::new(0.0, from), KeyFrame::new(1.0, to)]);
+            }
+        }
+        animation
+    }
+}
@@ -1,3 +1,4 @@
+use floem_renderer::tiny_skia::Pixmap;
 use glazier::{kurbo::Size, WindowBuilder};
 use leptos_reactive::{create_runtime, raw_scope_and_disposer, Scope};
 
@@ -9,6 +10,23 @@ pub fn launch<V: View + 'static>(app_view: impl Fn() -> V + 'static) {
     Application::new().window(app_view, None).run()
 }
 
+/// Renders a view off-screen at the given logical `size` and `scale` factor,
+/// without creating a window. The returned image can be encoded with
+/// [`Pixmap::encode_png`].
+pub fn render_to_image<V: View + 'static>(
+    app_view: impl FnOnce() -> V,
+    size: Size,
+    scale: f64,
+) -> Option<Pixmap> {
+    let runtime = create_runtime();
+    let (scope, _) = raw_scope_and_disposer(runtime);
+    let mut app = AppHandle::new(scope, app_view);
+    let image = app.render_to_image(size, scale);
+    drop(app);
+    runtime.dispose();
+    image
+}
+
 pub enum AppEvent {
     WillTerminate,
 }
@@ -91,3 +109,31 @@ impl Application {
         application.run(Some(Box::new(self)));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glazier::kurbo::Size;
+    use vello::peniko::Color;
+
+    use super::render_to_image;
+    use crate::{
+        style::Style,
+        views::{empty, Decorators},
+    };
+
+    #[test]
+    fn render_view_to_image() {
+        let image = render_to_image(
+            || empty().style(|| Style::BASE.size_px(10.0, 5.0).background(Color::RED)),
+            Size::new(20.0, 10.0),
+            2.0,
+        )
+        .unwrap();
+
+        assert_eq!((image.width(), image.height()), (40, 20));
+        let pixel = image.pixel(5, 5).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (255, 0, 0));
+        let pixel = image.pixel(30, 15).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (255, 255, 255));
+    }
+}
@@ -1,18 +1,24 @@
 use std::time::Duration;
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, borrow::Cow, collections::HashMap, ops::Range};
 
 use crate::animate::AnimValue;
+use accesskit::{Action, ActionRequest, NodeBuilder, NodeClassSet, Role, Tree, TreeUpdate};
+use floem_renderer::tiny_skia::Pixmap;
 use floem_renderer::Renderer;
-use glazier::kurbo::{Affine, Point, Rect, Vec2};
-use glazier::{FileDialogOptions, FileDialogToken, FileInfo, Scale, TimerToken, WinHandler};
+use glazier::kurbo::{Affine, Point, Rect, Size, Vec2};
+use glazier::text::{Action as ImeAction, Affinity, HitTestPoint, InputHandler, Selection};
+use glazier::{
+    FileDialogOptions, FileDialogToken, FileInfo, Scale, TextFieldToken, TimerToken, WinHandler,
+};
 use leptos_reactive::Scope;
 
 use crate::menu::Menu;
 use crate::{
     animate::{AnimPropKind, AnimUpdateMsg, AnimatedProp, Animation, SizeUnit},
+    clipboard::{Clipboard, CLIPBOARD},
     context::{
-        AppContextStore, AppState, EventCallback, EventCx, LayoutCx, PaintCx, PaintState,
-        ResizeCallback, ResizeListener, UpdateCx, APP_CONTEXT_STORE,
+        AccessibilityCx, AppContextStore, AppState, EventCallback, EventCx, LayoutCx, PaintCx,
+        PaintState, ResizeCallback, ResizeListener, UpdateCx, VirtualClock, APP_CONTEXT_STORE,
     },
     event::{Event, EventListener},
     ext_event::EXT_EVENT_HANDLER,
@@ -39,6 +45,13 @@ pub struct AppHandle<V: View> {
     paint_state: PaintState,
 
     file_dialogs: FileDialogs,
+
+    /// the root of the accessibility tree, which holds the nodes of the view
+    window_id: Id,
+    window_focused: bool,
+    accessibility_classes: NodeClassSet,
+    /// the focus last reported to the accessibility tree
+    accessibility_focus: Option<Id>,
 }
 
 #[derive(Copy, Clone)]
@@ -95,6 +108,15 @@ impl AppContext {
     pub fn new_id(&self) -> Id {
         self.id.new()
     }
+
+    /// Replaces the clipboard used by views on this thread
+    pub fn set_clipboard(clipboard: impl Clipboard + 'static) {
+        CLIPBOARD.with(|c| *c.borrow_mut() = Box::new(clipboard));
+    }
+
+    pub fn with_clipboard<R>(f: impl FnOnce(&mut dyn Clipboard) -> R) -> R {
+        CLIPBOARD.with(|c| f(c.borrow_mut().as_mut()))
+    }
 }
 
 pub enum StyleSelector {
@@ -183,9 +205,10 @@ impl<V: View> Drop for AppHandle<V> {
 
 impl<V: View> AppHandle<V> {
     pub fn new(scope: Scope, app_logic: impl FnOnce() -> V) -> Self {
+        let window_id = Id::next();
         let cx = AppContext {
             scope,
-            id: Id::next(),
+            id: window_id,
         };
 
         AppContext::set_current(cx);
@@ -198,6 +221,10 @@ impl<V: View> AppHandle<V> {
             paint_state: PaintState::new(),
             handle: Default::default(),
             file_dialogs: HashMap::new(),
+            window_id,
+            window_focused: true,
+            accessibility_classes: NodeClassSet::new(),
+            accessibility_focus: None,
         }
     }
 
@@ -245,6 +272,7 @@ impl<V: View> AppHandle<V> {
             paint_state: &mut self.paint_state,
             transform: Affine::IDENTITY,
             clip: None,
+            clip_depth: 0,
             color: None,
             font_size: None,
             font_family: None,
@@ -254,6 +282,7 @@ impl<V: View> AppHandle<V> {
             z_index: None,
             saved_transforms: Vec::new(),
             saved_clips: Vec::new(),
+            saved_clip_depths: Vec::new(),
             saved_colors: Vec::new(),
             saved_font_sizes: Vec::new(),
             saved_font_families: Vec::new(),
@@ -267,6 +296,37 @@ impl<V: View> AppHandle<V> {
         cx.paint_state.renderer.as_mut().unwrap().finish();
     }
 
+    /// Lays out and paints the view off-screen at the given logical `size` and
+    /// `scale` factor, and returns the painted image.
+    ///
+    /// This replaces the window renderer with a CPU one, so it's meant for
+    /// app handles that aren't connected to a window. Returns `None` if the
+    /// CPU renderer can't be created for `size`.
+    pub fn render_to_image(&mut self, size: Size, scale: f64) -> Option<Pixmap> {
+        let scale = Scale::new(scale * self.app_state.scale, scale * self.app_state.scale);
+        let renderer_size = size / self.app_state.scale;
+        match self.paint_state.renderer.as_ref() {
+            Some(crate::renderer::Renderer::TinySkia(_)) => {
+                self.paint_state.resize(scale, renderer_size);
+            }
+            _ => {
+                self.paint_state
+                    .connect_headless(scale, renderer_size)
+                    .ok()?;
+            }
+        }
+        self.app_state.update_scr_size_breakpt(size);
+        self.app_state.set_root_size(size);
+        self.layout();
+        self.process_update();
+        self.paint();
+        self.paint_state
+            .renderer
+            .as_ref()
+            .and_then(|renderer| renderer.pixmap())
+            .cloned()
+    }
+
     fn process_anim_update_messages(&mut self) -> ChangeFlags {
         let mut flags = ChangeFlags::empty();
         let msgs: Vec<AnimUpdateMsg> = ANIM_UPDATE_MESSAGES.with(|msgs| {
@@ -298,10 +358,38 @@ impl<V: View> AppHandle<V> {
         val: AnimValue,
     ) -> ChangeFlags {
         let layout = self.app_state.get_layout(view_id).unwrap();
+        let now = self.app_state.now();
         let view_state = self.app_state.view_state(view_id);
         let anim = view_state.animation.as_mut().unwrap();
         let prop = match kind {
-            AnimPropKind::Scale => todo!(),
+            AnimPropKind::Scale => {
+                let scale = view_state.computed_style.scale_x;
+                AnimatedProp::Scale {
+                    from: scale,
+                    to: val.get_f64(),
+                }
+            }
+            AnimPropKind::TranslateX => {
+                let translate_x = view_state.computed_style.translate_x;
+                AnimatedProp::TranslateX {
+                    from: translate_x,
+                    to: val.get_f64(),
+                }
+            }
+            AnimPropKind::TranslateY => {
+                let translate_y = view_state.computed_style.translate_y;
+                AnimatedProp::TranslateY {
+                    from: translate_y,
+                    to: val.get_f64(),
+                }
+            }
+            AnimPropKind::Rotate => {
+                let rotate = view_state.computed_style.rotate;
+                AnimatedProp::Rotate {
+                    from: rotate,
+                    to: val.get_f64(),
+                }
+            }
             AnimPropKind::Width => {
                 let width = layout.size.width;
                 AnimatedProp::Width {
@@ -319,19 +407,30 @@ impl<V: View> AppHandle<V> {
                 }
             }
             AnimPropKind::BorderRadius => {
-                let border_radius = view_state.computed_style.border_radius;
+                // the animation sets all four corners to one radius, starting
+                // from the top-left one
+                let border_radius = view_state.computed_style.border_top_left_radius;
                 AnimatedProp::BorderRadius {
                     from: border_radius as f64,
                     to: val.get_f64(),
                 }
             }
             AnimPropKind::BorderColor => {
-                let border_color = view_state.computed_style.border_color;
+                // the animation sets all four sides to one color, starting
+                // from the top one
+                let border_color = view_state.computed_style.border_top_color;
                 AnimatedProp::BorderColor {
                     from: border_color,
                     to: val.get_color(),
                 }
             }
+            AnimPropKind::Opacity => {
+                let opacity = view_state.computed_style.opacity;
+                AnimatedProp::Opacity {
+                    from: opacity as f64,
+                    to: val.get_f64(),
+                }
+            }
             AnimPropKind::Background => {
                 //TODO:  get from cx
                 let bg = view_state
@@ -360,7 +459,7 @@ impl<V: View> AppHandle<V> {
         // TODO: logic based on the old val to make the animation smoother when overriding an old
         // animation that was in progress
         anim.props_mut().insert(kind, prop);
-        anim.begin();
+        anim.begin_at(now);
 
         ChangeFlags::LAYOUT
     }
@@ -577,6 +676,27 @@ impl<V: View> AppHandle<V> {
         ANIM_UPDATE_MESSAGES.with(|m| !m.borrow().is_empty())
     }
 
+    pub(crate) fn view_id(&self) -> Id {
+        self.view.id()
+    }
+
+    pub(crate) fn app_state(&self) -> &AppState {
+        &self.app_state
+    }
+
+    pub(crate) fn app_state_mut(&mut self) -> &mut AppState {
+        &mut self.app_state
+    }
+
+    /// Sets up the app handle to run without a window, with timers
+    /// driven by a [`VirtualClock`].
+    pub(crate) fn connect_headless(&mut self, size: Size) {
+        self.app_state.virtual_clock = Some(VirtualClock::default());
+        self.app_state.update_scr_size_breakpt(size);
+        self.app_state.set_root_size(size);
+        self.idle();
+    }
+
     pub fn process_update(&mut self) {
         let mut flags = ChangeFlags::empty();
         loop {
@@ -604,9 +724,84 @@ impl<V: View> AppHandle<V> {
         if !flags.is_empty() {
             self.handle.invalidate();
         }
+
+        if flags.intersects(ChangeFlags::LAYOUT | ChangeFlags::ACCESSIBILITY)
+            || self.accessibility_focus != self.app_state.focus
+        {
+            self.accessibility_focus = self.app_state.focus;
+            let handle = self.handle.clone();
+            handle.update_accesskit_if_active(|| self.accessibility_tree());
+        }
+    }
+
+    /// Builds the whole accessibility tree, with the window as its root
+    pub(crate) fn accessibility_tree(&mut self) -> TreeUpdate {
+        let mut cx = AccessibilityCx {
+            app_state: &mut self.app_state,
+            classes: &mut self.accessibility_classes,
+            nodes: Vec::new(),
+        };
+        let children = self.view.accessibility_main(&mut cx);
+
+        let mut window = NodeBuilder::new(Role::Window);
+        window.set_children(children);
+        // the views report their bounds in logical coordinates before the app scale
+        let scale = self.handle.get_scale().map(|s| s.x()).unwrap_or(1.0) * cx.app_state.scale;
+        window.set_transform(accesskit::Affine::scale(scale));
+        cx.push_node(self.window_id, window);
+
+        let focus = cx
+            .app_state
+            .focus
+            .map(|id| id.to_node_id())
+            .filter(|node_id| cx.contains(*node_id))
+            .unwrap_or_else(|| self.window_id.to_node_id());
+        TreeUpdate {
+            nodes: cx.nodes,
+            tree: Some(Tree::new(self.window_id.to_node_id())),
+            focus: self.window_focused.then_some(focus),
+        }
+    }
+
+    /// Performs an action requested by an assistive technology
+    pub(crate) fn accessibility_action(&mut self, request: ActionRequest) {
+        let id = match Id::from_node_id(request.target) {
+            Some(id) => id,
+            None => return,
+        };
+        match request.action {
+            Action::Focus => {
+                if self.app_state.keyboard_navigatable.contains(&id) {
+                    id.request_focus();
+                }
+            }
+            Action::Default => {
+                let mut cx = EventCx {
+                    app_state: &mut self.app_state,
+                };
+                let rect = cx.app_state.view_state(id).layout_rect;
+                // a click in the middle of the view, as if it came from the pointer
+                let event = Event::PointerUp(glazier::PointerEvent {
+                    pos: rect.center() - rect.origin().to_vec2(),
+                    button: glazier::PointerButton::Left,
+                    count: 1,
+                    ..Default::default()
+                });
+                if let Some(action) = cx.get_event_listener(id, &EventListener::Click) {
+                    (*action)(&event);
+                }
+            }
+            _ => {}
+        }
+        self.process_update();
     }
 
     pub fn event(&mut self, event: Event) {
+        self.dispatch_event(event);
+    }
+
+    /// Sends the event to the views, returning if a view processed it
+    fn dispatch_event(&mut self, event: Event) -> bool {
         let event = event.scale(self.app_state.scale);
 
         let mut cx = EventCx {
@@ -631,9 +826,8 @@ impl<V: View> AppHandle<V> {
             cx.app_state.focus
         };
 
+        let mut processed = false;
         if event.needs_focus() {
-            let mut processed = false;
-
             if !processed {
                 if let Some(id) = cx.app_state.focus {
                     IDPATHS.with(|paths| {
@@ -655,8 +849,10 @@ impl<V: View> AppHandle<V> {
                             let backwards = mods.contains(glazier::Modifiers::SHIFT);
                             self.view.tab_navigation(cx.app_state, backwards);
                         } else if let glazier::KbKey::Character(character) = key {
-                            // 'I' displays some debug information
-                            if character.eq_ignore_ascii_case("i") {
+                            // 'I' displays some debug information, unless it's typed into a text field
+                            if character.eq_ignore_ascii_case("i")
+                                && cx.app_state.focused_text_field.is_none()
+                            {
                                 self.view.debug_tree();
                             }
                         }
@@ -769,6 +965,7 @@ impl<V: View> AppHandle<V> {
         }
 
         self.process_update();
+        processed
     }
 
     fn idle(&mut self) {
@@ -784,6 +981,7 @@ impl<V: View> WinHandler for AppHandle<V> {
         self.app_state.handle = handle.clone();
         self.paint_state.connect(handle);
         self.handle = handle.clone();
+        self.app_state.input_method = true;
         let size = handle.get_size();
         self.app_state.set_root_size(size);
         if let Some(idle_handle) = handle.get_idle_handle() {
@@ -828,8 +1026,10 @@ impl<V: View> WinHandler for AppHandle<V> {
 
     fn key_down(&mut self, event: glazier::KeyEvent) -> bool {
         assert_eq!(event.state, glazier::KeyState::Down);
-        self.event(Event::KeyDown(event));
-        true
+        let processed = self.dispatch_event(Event::KeyDown(event));
+        // keys no view processed go to the input method of the focused text field,
+        // which enters text through its input handler
+        processed || self.app_state.focused_text_field.is_none()
     }
 
     fn key_up(&mut self, event: glazier::KeyEvent) {
@@ -885,8 +1085,132 @@ impl<V: View> WinHandler for AppHandle<V> {
         self.handle.close();
     }
 
+    fn got_focus(&mut self) {
+        self.window_focused = true;
+        let handle = self.handle.clone();
+        handle.update_accesskit_if_active(|| self.accessibility_tree());
+    }
+
+    fn lost_focus(&mut self) {
+        self.window_focused = false;
+        let handle = self.handle.clone();
+        handle.update_accesskit_if_active(|| self.accessibility_tree());
+    }
+
+    fn acquire_input_lock(
+        &mut self,
+        token: TextFieldToken,
+        _mutable: bool,
+    ) -> Box<dyn InputHandler> {
+        // the platform can still hold the token of a text field whose view was
+        // removed, there's no text to edit then
+        match self.app_state.text_fields.get(&token) {
+            Some((_, handler)) => handler(),
+            None => Box::new(EmptyInputHandler),
+        }
+    }
+
+    fn release_input_lock(&mut self, _token: TextFieldToken) {
+        self.process_update();
+    }
+
+    fn accesskit_tree(&mut self) -> TreeUpdate {
+        self.accessibility_tree()
+    }
+
+    fn accesskit_action(&mut self, request: ActionRequest) {
+        self.accessibility_action(request);
+    }
+
     fn destroy(&mut self) {
         self.event(Event::WindowClosed);
         glazier::Application::global().quit();
     }
 }
+
+/// The input handler of a text field that's no longer registered, which has no
+/// text and ignores edits
+struct EmptyInputHandler;
+
+impl InputHandler for EmptyInputHandler {
+    fn selection(&self) -> Selection {
+        Selection::new(0, 0)
+    }
+
+    fn set_selection(&mut self, _selection: Selection) {}
+
+    fn composition_range(&self) -> Option<Range<usize>> {
+        None
+    }
+
+    fn set_composition_range(&mut self, _range: Option<Range<usize>>) {}
+
+    fn is_char_boundary(&self, i: usize) -> bool {
+        i == 0
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn slice(&self, _range: Range<usize>) -> Cow<str> {
+        "".into()
+    }
+
+    fn replace_range(&mut self, _range: Range<usize>, _text: &str) {}
+
+    fn hit_test_point(&self, _point: Point) -> HitTestPoint {
+        HitTestPoint::default()
+    }
+
+    fn line_range(&self, _index: usize, _affinity: Affinity) -> Range<usize> {
+        0..0
+    }
+
+    fn bounding_box(&self) -> Option<Rect> {
+        None
+    }
+
+    fn slice_bounding_box(&self, _range: Range<usize>) -> Option<Rect> {
+        None
+    }
+
+    fn handle_action(&mut self, _action: ImeAction) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use accesskit::Role;
+
+    use crate::{
+        test_harness::TestHarness,
+        views::{empty, label, stack},
+    };
+
+    #[test]
+    fn accessibility_tree_skips_views_without_nodes() {
+        let mut harness = TestHarness::new(
+            || stack(|| (label(|| "Hello".to_string()), empty())),
+            (100.0, 100.0),
+        );
+        let label_id = harness.root_id().first_child().unwrap();
+
+        let tree = harness.accessibility_tree();
+        let (window_id, window) = tree
+            .nodes
+            .iter()
+            .find(|(_, node)| node.role() == Role::Window)
+            .unwrap();
+        assert_eq!(tree.tree.unwrap().root, *window_id);
+        // the stack and the empty view have no nodes, so the label is the window's only child
+        assert_eq!(window.children(), &[label_id.to_node_id()]);
+
+        let (_, label) = tree
+            .nodes
+            .iter()
+            .find(|(id, _)| *id == label_id.to_node_id())
+            .unwrap();
+        assert_eq!(label.role(), Role::StaticText);
+        assert_eq!(label.name(), Some("Hello"));
+    }
+}
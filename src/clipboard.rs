@@ -0,0 +1,49 @@
+use std::cell::RefCell;
+
+use glazier::Application;
+
+thread_local! {
+    pub(crate) static CLIPBOARD: RefCell<Box<dyn Clipboard>> = RefCell::new(Box::new(SystemClipboard));
+}
+
+/// Where views like `TextInput` copy text to and paste it from.
+///
+/// The system clipboard is used by default, it can be replaced with
+/// [`AppContext::set_clipboard`](crate::AppContext::set_clipboard), for example
+/// with a [`MemoryClipboard`] in tests.
+pub trait Clipboard {
+    fn get_string(&mut self) -> Option<String>;
+
+    fn put_string(&mut self, s: &str);
+}
+
+/// The clipboard of the platform, through glazier
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn get_string(&mut self) -> Option<String> {
+        Application::try_global().and_then(|app| app.clipboard().get_string())
+    }
+
+    fn put_string(&mut self, s: &str) {
+        if let Some(app) = Application::try_global() {
+            app.clipboard().put_string(s);
+        }
+    }
+}
+
+/// A clipboard that only lives in memory, which doesn't need a running application
+#[derive(Default)]
+pub struct MemoryClipboard {
+    contents: Option<String>,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_string(&mut self) -> Option<String> {
+        self.contents.clone()
+    }
+
+    fn put_string(&mut self, s: &str) {
+        self.contents = Some(s.to_string());
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     collections::{HashMap, HashSet},
     ops::{Deref, DerefMut},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use floem_renderer::{
@@ -10,7 +10,8 @@ use floem_renderer::{
 };
 use glazier::{
     kurbo::{Affine, Point, Rect, Shape, Size, Vec2},
-    PointerEvent, Scale, TimerToken,
+    text::InputHandler,
+    PointerEvent, Scale, TextFieldToken, TimerToken,
 };
 use taffy::{
     prelude::{Layout, Node},
@@ -35,6 +36,7 @@ thread_local! {
 
 pub type EventCallback = dyn Fn(&Event) -> bool;
 pub type ResizeCallback = dyn Fn(Point, Rect);
+pub type InputHandlerFactory = dyn Fn() -> Box<dyn InputHandler>;
 
 pub struct AppContextStore {
     pub cx: AppContext,
@@ -70,6 +72,9 @@ pub struct ViewState {
     pub(crate) viewport: Option<Rect>,
     pub(crate) layout_rect: Rect,
     pub(crate) animation: Option<Animation>,
+    /// The enter or exit transition of a child of a list or tab, which runs on
+    /// top of the view's own animation
+    pub(crate) transition: Option<Animation>,
     pub(crate) base_style: Option<Style>,
     pub(crate) style: Style,
     pub(crate) dragging_style: Option<Style>,
@@ -94,6 +99,7 @@ impl ViewState {
             layout_rect: Rect::ZERO,
             request_layout: true,
             animation: None,
+            transition: None,
             base_style: None,
             style: Style::BASE,
             combined_style: Style::BASE,
@@ -117,6 +123,7 @@ impl ViewState {
         view_style: Option<Style>,
         interact_state: InteractionState,
         screen_size_bp: ScreenSizeBp,
+        now: Instant,
     ) {
         let mut computed_style = if let Some(view_style) = view_style {
             if let Some(base_style) = self.base_style.clone() {
@@ -169,43 +176,11 @@ impl ViewState {
             }
         }
 
-        'anim: {
-            if let Some(animation) = self.animation.as_mut() {
-                if animation.is_completed() && animation.is_auto_reverse() {
-                    break 'anim;
-                }
-
-                let props = animation.props();
-
-                for (kind, _) in props {
-                    let val = animation
-                        .animate_prop(animation.elapsed().unwrap_or(Duration::ZERO), &kind);
-                    match kind {
-                        AnimPropKind::Width => {
-                            computed_style = computed_style.width_px(val.get_f32());
-                        }
-                        AnimPropKind::Height => {
-                            computed_style = computed_style.height_px(val.get_f32());
-                        }
-                        AnimPropKind::Background => {
-                            computed_style = computed_style.background(val.get_color());
-                        }
-                        AnimPropKind::Color => {
-                            computed_style = computed_style.color(val.get_color());
-                        }
-                        AnimPropKind::BorderRadius => {
-                            computed_style = computed_style.border_radius(val.get_f32());
-                        }
-                        AnimPropKind::BorderColor => {
-                            computed_style = computed_style.border_color(val.get_color());
-                        }
-                        AnimPropKind::Scale => todo!(),
-                    }
-                }
-
-                animation.advance();
-                debug_assert!(!animation.is_idle());
-            }
+        for animation in [self.animation.as_mut(), self.transition.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            computed_style = animate_style(animation, computed_style, now);
         }
 
         self.combined_style = computed_style.clone();
@@ -224,12 +199,103 @@ impl ViewState {
     }
 }
 
+/// Applies the animated properties to `style`, and advances the animation to `now`
+fn animate_style(animation: &mut Animation, mut computed_style: Style, now: Instant) -> Style {
+    if animation.is_completed() && animation.is_auto_reverse() {
+        return computed_style;
+    }
+
+    for kind in animation.prop_kinds() {
+        let val = animation.animate_prop(animation.elapsed_at(now).unwrap_or(Duration::ZERO), kind);
+        match kind {
+            AnimPropKind::Width => {
+                computed_style = computed_style.width_px(val.get_f32());
+            }
+            AnimPropKind::Height => {
+                computed_style = computed_style.height_px(val.get_f32());
+            }
+            AnimPropKind::Background => {
+                computed_style = computed_style.background(val.get_color());
+            }
+            AnimPropKind::Color => {
+                computed_style = computed_style.color(val.get_color());
+            }
+            AnimPropKind::BorderRadius => {
+                computed_style = computed_style.border_radius(val.get_f32());
+            }
+            AnimPropKind::BorderColor => {
+                computed_style = computed_style.border_color(val.get_color());
+            }
+            AnimPropKind::Opacity => {
+                computed_style = computed_style.opacity(val.get_f32());
+            }
+            AnimPropKind::Scale => {
+                computed_style = computed_style.scale(val.get_f64());
+            }
+            AnimPropKind::TranslateX => {
+                computed_style = computed_style.translate_x(val.get_f64());
+            }
+            AnimPropKind::TranslateY => {
+                computed_style = computed_style.translate_y(val.get_f64());
+            }
+            AnimPropKind::Rotate => {
+                computed_style = computed_style.rotate(val.get_f64());
+            }
+        }
+    }
+
+    animation.advance_at(now);
+    debug_assert!(!animation.is_idle());
+    computed_style
+}
+
 pub struct DragState {
     pub(crate) id: Id,
     pub(crate) offset: Vec2,
     pub(crate) released_at: Option<std::time::Instant>,
 }
 
+/// A clock for timers requested without a window, which only moves
+/// forward when it's advanced explicitly. Animations run on it too.
+pub(crate) struct VirtualClock {
+    /// The instant the clock started at, which animations count from
+    start: Instant,
+    pub(crate) now: Duration,
+    pub(crate) timers: Vec<(Duration, TimerToken)>,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            now: Duration::ZERO,
+            timers: Vec::new(),
+        }
+    }
+}
+
+impl VirtualClock {
+    fn request_timer(&mut self, deadline: Duration) -> TimerToken {
+        let token = TimerToken::next();
+        self.timers.push((self.now + deadline, token));
+        token
+    }
+
+    /// Removes the earliest timer that's due at or before `until`,
+    /// moving the clock forward to it.
+    pub(crate) fn next_due(&mut self, until: Duration) -> Option<TimerToken> {
+        let (index, _) = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, (due, _))| *due <= until)
+            .min_by_key(|(_, (due, _))| *due)?;
+        let (due, token) = self.timers.remove(index);
+        self.now = self.now.max(due);
+        Some(token)
+    }
+}
+
 pub struct AppState {
     pub(crate) handle: glazier::WindowHandle,
     /// keyboard focus
@@ -258,6 +324,15 @@ pub struct AppState {
     pub(crate) keyboard_navigation: bool,
     pub(crate) contex_menu: HashMap<u32, Box<dyn Fn()>>,
     pub(crate) timers: HashMap<TimerToken, Box<dyn FnOnce()>>,
+    /// Replaces the window's timers when running headless
+    pub(crate) virtual_clock: Option<VirtualClock>,
+    /// Set when connected to a window, whose input method then enters the text
+    /// of the focused text field
+    pub(crate) input_method: bool,
+    /// The input handlers of the text fields registered with the window, with
+    /// the views they belong to
+    pub(crate) text_fields: HashMap<TextFieldToken, (Id, Box<InputHandlerFactory>)>,
+    pub(crate) focused_text_field: Option<TextFieldToken>,
 }
 
 impl Default for AppState {
@@ -293,6 +368,10 @@ impl AppState {
             grid_breakpts: GridBreakpoints::default(),
             contex_menu: HashMap::new(),
             timers: HashMap::new(),
+            virtual_clock: None,
+            input_method: false,
+            text_fields: HashMap::new(),
+            focused_text_field: None,
         }
     }
 
@@ -307,11 +386,11 @@ impl AppState {
             .clone()
             .into_iter()
             .filter(|id| {
-                let anim = &self.view_state(*id).animation;
-                if let Some(anim) = anim {
-                    return !anim.is_completed();
-                }
-                false
+                let view_state = self.view_state(*id);
+                [&view_state.animation, &view_state.transition]
+                    .into_iter()
+                    .flatten()
+                    .any(|anim| !anim.is_completed())
             })
             .collect()
     }
@@ -378,8 +457,17 @@ impl AppState {
     pub(crate) fn compute_style(&mut self, id: Id, view_style: Option<Style>) {
         let interact_state = self.get_interact_state(&id);
         let screen_size_bp = self.screen_size_bp;
+        let now = self.now();
         let view_state = self.view_state(id);
-        view_state.compute_style(view_style, interact_state, screen_size_bp);
+        view_state.compute_style(view_style, interact_state, screen_size_bp, now);
+    }
+
+    /// The current time, from the virtual clock when there is one
+    pub(crate) fn now(&self) -> Instant {
+        match self.virtual_clock.as_ref() {
+            Some(clock) => clock.start + clock.now,
+            None => Instant::now(),
+        }
     }
 
     pub(crate) fn get_computed_style(&mut self, id: Id) -> &ComputedStyle {
@@ -411,7 +499,11 @@ impl AppState {
     }
 
     pub(crate) fn request_timer(&mut self, deadline: Duration, action: Box<dyn FnOnce()>) {
-        let token = self.handle.request_timer(deadline);
+        let token = if let Some(clock) = self.virtual_clock.as_mut() {
+            clock.request_timer(deadline)
+        } else {
+            self.handle.request_timer(deadline)
+        };
         self.timers.insert(token, action);
     }
 
@@ -428,6 +520,49 @@ impl AppState {
             .copied()
     }
 
+    /// Registers a text field of the view `id` with the window, whose input
+    /// method edits it with the input handlers `handler` makes
+    pub(crate) fn add_text_field(
+        &mut self,
+        id: Id,
+        handler: Box<InputHandlerFactory>,
+    ) -> TextFieldToken {
+        let token = self.handle.add_text_field();
+        self.text_fields.insert(token, (id, handler));
+        token
+    }
+
+    /// Unregisters the text field of `token`, so the input method stops editing it
+    pub(crate) fn remove_text_field(&mut self, token: TextFieldToken) {
+        if self.focused_text_field == Some(token) {
+            self.set_focused_text_field(None);
+        }
+        if self.text_fields.remove(&token).is_some() {
+            self.handle.remove_text_field(token);
+        }
+    }
+
+    /// Unregisters the text fields of the view `id`, when it's removed
+    pub(crate) fn remove_text_fields_of(&mut self, id: Id) {
+        let tokens: Vec<TextFieldToken> = self
+            .text_fields
+            .iter()
+            .filter(|(_, (view, _))| *view == id)
+            .map(|(token, _)| *token)
+            .collect();
+        for token in tokens {
+            self.remove_text_field(token);
+        }
+    }
+
+    /// Points the window's input method at the text field of `token`, or at none
+    pub(crate) fn set_focused_text_field(&mut self, token: Option<TextFieldToken>) {
+        if self.focused_text_field != token {
+            self.focused_text_field = token;
+            self.handle.set_focused_text_field(token);
+        }
+    }
+
     pub(crate) fn get_layout_rect(&mut self, id: Id) -> Rect {
         self.view_state(id).layout_rect
     }
@@ -587,6 +722,14 @@ impl<'a> EventCx<'a> {
             .and_then(|s| s.event_listeners.get(listner))
     }
 
+    /// The transform from the style of the view, relative to its layout location
+    pub(crate) fn view_transform(&self, id: Id) -> Affine {
+        match (self.get_computed_style(id), self.get_size(id)) {
+            (Some(style), Some(size)) => style.transform(size),
+            _ => Affine::IDENTITY,
+        }
+    }
+
     pub(crate) fn offset_event(&self, id: Id, event: Event) -> Event {
         let viewport = self
             .app_state
@@ -595,10 +738,16 @@ impl<'a> EventCx<'a> {
             .and_then(|view| view.viewport);
 
         if let Some(layout) = self.get_layout(id) {
-            event.offset((
+            let event = event.offset((
                 layout.location.x as f64 - viewport.map(|rect| rect.x0).unwrap_or(0.0),
                 layout.location.y as f64 - viewport.map(|rect| rect.y0).unwrap_or(0.0),
-            ))
+            ));
+            let transform = self.view_transform(id);
+            if transform == Affine::IDENTITY {
+                event
+            } else {
+                event.transform(transform.inverse())
+            }
         } else {
             event
         }
@@ -613,13 +762,10 @@ impl<'a> EventCx<'a> {
         if let Some(point) = event.point() {
             let layout_rect = self.app_state.get_layout_rect(id);
             if let Some(layout) = self.get_layout(id) {
-                if layout_rect
-                    .with_origin(Point::new(
-                        layout.location.x as f64,
-                        layout.location.y as f64,
-                    ))
-                    .contains(point)
-                {
+                let location = Point::new(layout.location.x as f64, layout.location.y as f64);
+                // the point in the view's coordinates, before its transform
+                let point = self.view_transform(id).inverse() * (point - location.to_vec2());
+                if layout_rect.with_origin(Point::ZERO).contains(point) {
                     return true;
                 }
             }
@@ -780,7 +926,11 @@ pub struct PaintCx<'a> {
     pub(crate) app_state: &'a mut AppState,
     pub(crate) paint_state: &'a mut PaintState,
     pub(crate) transform: Affine,
+    /// The bounding box of the clip in local coordinates, to skip painting
+    /// what's clipped away
     pub(crate) clip: Option<Rect>,
+    /// How many clips are pushed on the renderer
+    pub(crate) clip_depth: usize,
     pub(crate) color: Option<Color>,
     pub(crate) font_size: Option<f32>,
     pub(crate) font_family: Option<String>,
@@ -790,6 +940,7 @@ pub struct PaintCx<'a> {
     pub(crate) z_index: Option<i32>,
     pub(crate) saved_transforms: Vec<Affine>,
     pub(crate) saved_clips: Vec<Option<Rect>>,
+    pub(crate) saved_clip_depths: Vec<usize>,
     pub(crate) saved_colors: Vec<Option<Color>>,
     pub(crate) saved_font_sizes: Vec<Option<f32>>,
     pub(crate) saved_font_families: Vec<Option<String>>,
@@ -803,6 +954,7 @@ impl<'a> PaintCx<'a> {
     pub fn save(&mut self) {
         self.saved_transforms.push(self.transform);
         self.saved_clips.push(self.clip);
+        self.saved_clip_depths.push(self.clip_depth);
         self.saved_colors.push(self.color);
         self.saved_font_sizes.push(self.font_size);
         self.saved_font_families.push(self.font_family.clone());
@@ -815,6 +967,7 @@ impl<'a> PaintCx<'a> {
     pub fn restore(&mut self) {
         self.transform = self.saved_transforms.pop().unwrap_or_default();
         self.clip = self.saved_clips.pop().unwrap_or_default();
+        let clip_depth = self.saved_clip_depths.pop().unwrap_or_default();
         self.color = self.saved_colors.pop().unwrap_or_default();
         self.font_size = self.saved_font_sizes.pop().unwrap_or_default();
         self.font_family = self.saved_font_families.pop().unwrap_or_default();
@@ -829,10 +982,10 @@ impl<'a> PaintCx<'a> {
         } else {
             renderer.set_z_index(0);
         }
-        if let Some(rect) = self.clip {
-            renderer.clip(&rect);
-        } else {
-            renderer.clear_clip();
+        // the clips pushed since the save are popped off the renderer's stack
+        while self.clip_depth > clip_depth {
+            renderer.pop_clip();
+            self.clip_depth -= 1;
         }
     }
 
@@ -860,6 +1013,8 @@ impl<'a> PaintCx<'a> {
         self.app_state.get_computed_style(id)
     }
 
+    /// Clips what's painted to `shape`, within the current clip, until the
+    /// matching [`PaintCx::restore`].
     pub fn clip(&mut self, shape: &impl Shape) {
         let rect = shape.bounding_box();
         let rect = if let Some(existing) = self.clip {
@@ -868,14 +1023,19 @@ impl<'a> PaintCx<'a> {
             rect
         };
         self.clip = Some(rect);
-        self.paint_state.renderer.as_mut().unwrap().clip(&rect);
+        self.clip_depth += 1;
+        self.paint_state.renderer.as_mut().unwrap().push_clip(shape);
+    }
+
+    /// Paints without any clip until the matching [`PaintCx::restore`].
+    pub fn clear_clip(&mut self) {
+        self.clip = None;
+        self.clip_depth += 1;
+        self.paint_state.renderer.as_mut().unwrap().push_unclipped();
     }
 
     pub fn offset(&mut self, offset: (f64, f64)) {
-        let mut new = self.transform.as_coeffs();
-        new[4] += offset.0;
-        new[5] += offset.1;
-        self.transform = Affine::new(new);
+        self.transform *= Affine::translate(offset);
         self.paint_state
             .renderer
             .as_mut()
@@ -889,10 +1049,12 @@ impl<'a> PaintCx<'a> {
     pub fn transform(&mut self, id: Id) -> Size {
         if let Some(layout) = self.get_layout(id) {
             let offset = layout.location;
-            let mut new = self.transform.as_coeffs();
-            new[4] += offset.x as f64;
-            new[5] += offset.y as f64;
-            self.transform = Affine::new(new);
+            let size = Size::new(layout.size.width as f64, layout.size.height as f64);
+            let view_transform = self.app_state.get_computed_style(id).transform(size);
+
+            // the layout location is in the parent's coordinates, like in `offset_event`
+            let transform = Affine::translate((offset.x as f64, offset.y as f64)) * view_transform;
+            self.transform *= transform;
             self.paint_state
                 .renderer
                 .as_mut()
@@ -900,11 +1062,10 @@ impl<'a> PaintCx<'a> {
                 .transform(self.transform);
 
             if let Some(rect) = self.clip.as_mut() {
-                *rect =
-                    rect.with_origin(rect.origin() - Vec2::new(offset.x as f64, offset.y as f64));
+                *rect = transform.inverse().transform_rect_bbox(*rect);
             }
 
-            Size::new(layout.size.width as f64, layout.size.height as f64)
+            size
         } else {
             Size::ZERO
         }
@@ -948,6 +1109,11 @@ impl PaintState {
         self.renderer = Some(crate::renderer::Renderer::new(handle));
     }
 
+    pub(crate) fn connect_headless(&mut self, scale: Scale, size: Size) -> anyhow::Result<()> {
+        self.renderer = Some(crate::renderer::Renderer::new_headless(scale, size)?);
+        Ok(())
+    }
+
     pub(crate) fn resize(&mut self, scale: Scale, size: Size) {
         if let Some(renderer) = self.renderer.as_mut() {
             renderer.resize(scale, size);
@@ -971,6 +1137,24 @@ impl<'a> UpdateCx<'a> {
     }
 }
 
+/// Collects the accessibility nodes of the view tree, see [`View::accessibility`](crate::view::View::accessibility)
+pub struct AccessibilityCx<'a> {
+    pub(crate) app_state: &'a mut AppState,
+    pub(crate) classes: &'a mut accesskit::NodeClassSet,
+    pub(crate) nodes: Vec<(accesskit::NodeId, accesskit::Node)>,
+}
+
+impl<'a> AccessibilityCx<'a> {
+    pub(crate) fn push_node(&mut self, id: Id, node: accesskit::NodeBuilder) {
+        let node = node.build(self.classes);
+        self.nodes.push((id.to_node_id(), node));
+    }
+
+    pub(crate) fn contains(&self, node_id: accesskit::NodeId) -> bool {
+        self.nodes.iter().any(|(id, _)| *id == node_id)
+    }
+}
+
 impl Deref for PaintCx<'_> {
     type Target = crate::renderer::Renderer;
 
@@ -984,3 +1168,91 @@ impl DerefMut for PaintCx<'_> {
         self.paint_state.renderer.as_mut().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use vello::peniko::Color;
+
+    use crate::{
+        style::Style,
+        test_harness::TestHarness,
+        views::{container, empty, stack, Decorators},
+    };
+
+    #[test]
+    fn clicks_hit_transformed_views() {
+        let clicks = Rc::new(Cell::new(0));
+        let button_clicks = clicks.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                stack(move || {
+                    (empty()
+                        .style(|| {
+                            Style::BASE
+                                .size_px(50.0, 50.0)
+                                .translate(100.0, 0.0)
+                                .scale(2.0)
+                        })
+                        .on_click(move |_| {
+                            button_clicks.set(button_clicks.get() + 1);
+                            true
+                        }),)
+                })
+                .style(|| Style::BASE.size_px(200.0, 200.0))
+            },
+            (200.0, 200.0),
+        );
+
+        // where the view would be without its transform
+        harness.click((10.0, 10.0));
+        assert_eq!(clicks.get(), 0);
+
+        // scaled around its center, the view now covers 75..175 by -25..75
+        harness.click((80.0, 60.0));
+        assert_eq!(clicks.get(), 1);
+        harness.click((170.0, 5.0));
+        assert_eq!(clicks.get(), 2);
+    }
+
+    #[test]
+    fn children_of_transformed_views_paint_where_they_are_hit() {
+        let clicks = Rc::new(Cell::new(0));
+        let child_clicks = clicks.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                stack(move || {
+                    (container(move || {
+                        empty()
+                            .style(|| Style::BASE.size_px(10.0, 10.0).background(Color::RED))
+                            .on_click(move |_| {
+                                child_clicks.set(child_clicks.get() + 1);
+                                true
+                            })
+                    })
+                    .style(|| Style::BASE.size_px(50.0, 50.0).padding_px(10.0).scale(2.0)),)
+                })
+                .style(|| Style::BASE.size_px(200.0, 200.0).padding_px(50.0))
+            },
+            (200.0, 200.0),
+        );
+
+        // the parent is scaled around its center at 75, 75, so the child at 60..70
+        // covers 45..65
+        harness.click((47.0, 47.0));
+        assert_eq!(clicks.get(), 1);
+        harness.click((67.0, 67.0));
+        assert_eq!(clicks.get(), 1);
+
+        let image = harness.render(1.0).unwrap();
+        let pixel = |x, y| {
+            let pixel = image.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        assert_eq!(pixel(47, 47), (255, 0, 0));
+        assert_eq!(pixel(62, 62), (255, 0, 0));
+        assert_eq!(pixel(67, 67), (255, 255, 255));
+        assert_eq!(pixel(40, 40), (255, 255, 255));
+    }
+}
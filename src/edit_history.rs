@@ -0,0 +1,170 @@
+//! # Edit history
+//! An undo and redo stack for editable views. The history stores snapshots of the
+//! state from before each edit, so a snapshot can hold anything needed to restore
+//! the view, such as its text together with the selection:
+//! ```rust,ignore
+//! let before = self.snapshot();
+//! self.insert(text);
+//! self.history.record(before, EditKind::Insert);
+//! // later, on Ctrl+Z
+//! if let Some(state) = self.history.undo(self.snapshot()) {
+//!     self.restore(state);
+//! }
+//! ```
+//! Consecutive edits of the same kind are coalesced into one transaction, which
+//! is undone as a whole, until [`EditHistory::end_group`] is called or an edit
+//! of another kind is recorded.
+
+/// The kind of an edit, used to decide which edits are grouped together
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditKind {
+    /// Typing, consecutive inserts are grouped
+    Insert,
+    /// Deleting with backspace or delete, consecutive deletes are grouped
+    Delete,
+    /// An edit that is always a transaction of its own, like a paste
+    Other,
+}
+
+pub struct EditHistory<T> {
+    undo_stack: Vec<T>,
+    redo_stack: Vec<T>,
+    /// the kind of the transaction that new edits can still be added to
+    open_group: Option<EditKind>,
+    limit: usize,
+}
+
+impl<T> Default for EditHistory<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EditHistory<T> {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            open_group: None,
+            limit: 100,
+        }
+    }
+
+    /// The number of transactions kept, the oldest ones are dropped first
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+
+    /// Records `before`, the state from before an edit of `kind`. When the edit
+    /// continues the open group, it's merged into that transaction, and `before`
+    /// is dropped since the transaction already knows where it started.
+    pub fn record(&mut self, before: T, kind: EditKind) {
+        self.redo_stack.clear();
+        if kind != EditKind::Other && self.open_group == Some(kind) {
+            return;
+        }
+
+        self.undo_stack.push(before);
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.remove(0);
+        }
+        self.open_group = if kind == EditKind::Other {
+            None
+        } else {
+            Some(kind)
+        };
+    }
+
+    /// Closes the open transaction, so the next edit starts a new one.
+    /// Views call this when the cursor moves away from where the edits happened.
+    pub fn end_group(&mut self) {
+        self.open_group = None;
+    }
+
+    /// Reverts the last transaction, returning the state to restore. `current`
+    /// is kept so the transaction can be redone.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        self.open_group = None;
+        Some(state)
+    }
+
+    /// Applies the last undone transaction again, returning the state to restore
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        self.open_group = None;
+        Some(state)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.open_group = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EditHistory, EditKind};
+
+    #[test]
+    fn edits_of_the_same_kind_are_coalesced() {
+        let mut history = EditHistory::new();
+        history.record("", EditKind::Insert);
+        history.record("a", EditKind::Insert);
+        history.record("ab", EditKind::Delete);
+
+        assert_eq!(history.undo("a"), Some("ab"));
+        assert_eq!(history.undo("ab"), Some(""));
+        assert_eq!(history.undo(""), None);
+    }
+
+    #[test]
+    fn end_group_starts_a_new_transaction() {
+        let mut history = EditHistory::new();
+        history.record("", EditKind::Insert);
+        history.end_group();
+        history.record("a", EditKind::Insert);
+
+        assert_eq!(history.undo("ab"), Some("a"));
+        assert_eq!(history.undo("a"), Some(""));
+    }
+
+    #[test]
+    fn redo_is_cleared_by_new_edits() {
+        let mut history = EditHistory::new();
+        history.record("", EditKind::Other);
+        history.record("a", EditKind::Other);
+
+        assert_eq!(history.undo("ab"), Some("a"));
+        assert_eq!(history.redo("a"), Some("ab"));
+        assert_eq!(history.undo("ab"), Some("a"));
+        assert!(history.can_redo());
+
+        history.record("a", EditKind::Insert);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn oldest_transactions_are_dropped_over_the_limit() {
+        let mut history = EditHistory::new().limit(2);
+        history.record("", EditKind::Other);
+        history.record("a", EditKind::Other);
+        history.record("ab", EditKind::Other);
+
+        assert_eq!(history.undo("abc"), Some("ab"));
+        assert_eq!(history.undo("ab"), Some("a"));
+        assert_eq!(history.undo("a"), None);
+    }
+}
@@ -1,5 +1,5 @@
 use glazier::{
-    kurbo::{Point, Size},
+    kurbo::{Affine, Point, Size},
     KeyEvent, PointerEvent,
 };
 
@@ -143,6 +143,23 @@ impl Event {
         self
     }
 
+    pub fn transform(mut self, transform: Affine) -> Event {
+        match &mut self {
+            Event::PointerDown(pointer_event)
+            | Event::PointerUp(pointer_event)
+            | Event::PointerMove(pointer_event)
+            | Event::PointerWheel(pointer_event) => {
+                pointer_event.pos = transform * pointer_event.pos;
+            }
+            Event::KeyDown(_)
+            | Event::KeyUp(_)
+            | Event::WindowClosed
+            | Event::WindowResized(_)
+            | Event::WindowMoved(_) => {}
+        }
+        self
+    }
+
     pub fn listener(&self) -> Option<EventListener> {
         match self {
             Event::PointerDown(_) => Some(EventListener::PointerDown),
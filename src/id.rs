@@ -46,6 +46,17 @@ impl Id {
         self.0
     }
 
+    pub(crate) fn to_node_id(self) -> accesskit::NodeId {
+        self.0.into()
+    }
+
+    pub(crate) fn from_node_id(node_id: accesskit::NodeId) -> Option<Id> {
+        u64::try_from(node_id.0.get())
+            .ok()
+            .and_then(NonZeroU64::new)
+            .map(Id)
+    }
+
     pub fn new(&self) -> Id {
         let mut id_path =
             IDPATHS.with(|id_paths| id_paths.borrow().get(self).cloned().unwrap_or_default());
@@ -1,22 +1,28 @@
 mod app;
 mod app_handle;
+pub mod clipboard;
 pub mod context;
+pub mod edit_history;
 pub mod event;
 pub mod ext_event;
 pub mod id;
 pub mod menu;
 pub mod renderer;
 pub mod responsive;
+pub mod snapshot;
 pub mod style;
+pub mod test_harness;
 pub mod view;
 pub mod view_tuple;
 pub mod views;
 pub mod window;
 pub mod animate;
 
-pub use app::{launch, AppEvent, Application};
+pub use accesskit;
+pub use app::{launch, render_to_image, AppEvent, Application};
 pub use app_handle::AppContext;
 pub use floem_renderer::cosmic_text;
+pub use floem_renderer::tiny_skia;
 pub use floem_renderer::Renderer;
 pub use glazier;
 pub use leptos_reactive as reactive;
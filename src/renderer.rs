@@ -1,13 +1,18 @@
+use anyhow::Result;
+
 use crate::cosmic_text::TextLayout;
+use floem_renderer::tiny_skia::Pixmap;
+use floem_tiny_skia::TinySkiaRenderer;
 use floem_vger::VgerRenderer;
 use glazier::{
-    kurbo::{Affine, Rect, Shape, Size},
+    kurbo::{Affine, Rect, RoundedRectRadii, Shape, Size},
     Scalable, Scale, WindowHandle,
 };
-use vello::peniko::BrushRef;
+use vello::peniko::{BrushRef, Color, Stroke};
 
 pub enum Renderer {
     Vger(VgerRenderer),
+    TinySkia(TinySkiaRenderer),
 }
 
 impl Renderer {
@@ -19,16 +24,36 @@ impl Renderer {
         )
     }
 
+    /// Creates a CPU renderer that paints into an in-memory image instead of a window
+    pub fn new_headless(scale: Scale, size: Size) -> Result<Self> {
+        let size = size.to_px(scale).round();
+        Ok(Self::TinySkia(TinySkiaRenderer::new(
+            size.width as u32,
+            size.height as u32,
+            scale.x(),
+        )?))
+    }
+
+    /// The last painted image, if this renderer paints off-screen
+    pub fn pixmap(&self) -> Option<&Pixmap> {
+        match self {
+            Renderer::Vger(_) => None,
+            Renderer::TinySkia(r) => Some(r.pixmap()),
+        }
+    }
+
     pub fn resize(&mut self, scale: Scale, size: Size) {
-        let size = size.to_px(scale);
+        let size = size.to_px(scale).round();
         match self {
             Renderer::Vger(r) => r.resize(size.width as u32, size.height as u32, scale.x()),
+            Renderer::TinySkia(r) => r.resize(size.width as u32, size.height as u32, scale.x()),
         }
     }
 
     pub fn set_scale(&mut self, scale: Scale) {
         match self {
             Renderer::Vger(r) => r.set_scale(scale.x()),
+            Renderer::TinySkia(r) => r.set_scale(scale.x()),
         }
     }
 }
@@ -39,29 +64,52 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(r) => {
                 r.begin();
             }
+            Renderer::TinySkia(r) => {
+                r.begin();
+            }
         }
     }
 
-    fn clip(&mut self, shape: &impl Shape) {
+    fn push_clip(&mut self, shape: &impl Shape) {
         match self {
             Renderer::Vger(v) => {
-                v.clip(shape);
+                v.push_clip(shape);
+            }
+            Renderer::TinySkia(t) => {
+                t.push_clip(shape);
             }
         }
     }
 
-    fn clear_clip(&mut self) {
+    fn push_unclipped(&mut self) {
         match self {
             Renderer::Vger(v) => {
-                v.clear_clip();
+                v.push_unclipped();
+            }
+            Renderer::TinySkia(t) => {
+                t.push_unclipped();
             }
         }
     }
 
-    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, width: f64) {
+    fn pop_clip(&mut self) {
         match self {
             Renderer::Vger(v) => {
-                v.stroke(shape, brush, width);
+                v.pop_clip();
+            }
+            Renderer::TinySkia(t) => {
+                t.pop_clip();
+            }
+        }
+    }
+
+    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, stroke: &Stroke) {
+        match self {
+            Renderer::Vger(v) => {
+                v.stroke(shape, brush, stroke);
+            }
+            Renderer::TinySkia(t) => {
+                t.stroke(shape, brush, stroke);
             }
         }
     }
@@ -75,6 +123,9 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(v) => {
                 v.fill(path, brush);
             }
+            Renderer::TinySkia(t) => {
+                t.fill(path, brush);
+            }
         }
     }
 
@@ -83,6 +134,9 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(v) => {
                 v.draw_text(layout, pos);
             }
+            Renderer::TinySkia(t) => {
+                t.draw_text(layout, pos);
+            }
         }
     }
 
@@ -96,6 +150,59 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(v) => {
                 v.draw_svg(svg, rect, brush);
             }
+            Renderer::TinySkia(t) => {
+                t.draw_svg(svg, rect, brush);
+            }
+        }
+    }
+
+    fn draw_img(&mut self, img: floem_renderer::Img<'_>, rect: Rect) {
+        match self {
+            Renderer::Vger(v) => {
+                v.draw_img(img, rect);
+            }
+            Renderer::TinySkia(t) => {
+                t.draw_img(img, rect);
+            }
+        }
+    }
+
+    fn push_layer(&mut self, opacity: f32) {
+        match self {
+            Renderer::Vger(v) => {
+                v.push_layer(opacity);
+            }
+            Renderer::TinySkia(t) => {
+                t.push_layer(opacity);
+            }
+        }
+    }
+
+    fn pop_layer(&mut self) {
+        match self {
+            Renderer::Vger(v) => {
+                v.pop_layer();
+            }
+            Renderer::TinySkia(t) => {
+                t.pop_layer();
+            }
+        }
+    }
+
+    fn draw_box_shadow(
+        &mut self,
+        rect: Rect,
+        radii: RoundedRectRadii,
+        color: Color,
+        blur_radius: f64,
+    ) {
+        match self {
+            Renderer::Vger(v) => {
+                v.draw_box_shadow(rect, radii, color, blur_radius);
+            }
+            Renderer::TinySkia(t) => {
+                t.draw_box_shadow(rect, radii, color, blur_radius);
+            }
         }
     }
 
@@ -104,6 +211,9 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(v) => {
                 v.transform(transform);
             }
+            Renderer::TinySkia(t) => {
+                t.transform(transform);
+            }
         }
     }
 
@@ -112,6 +222,9 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(v) => {
                 v.set_z_index(z_index);
             }
+            Renderer::TinySkia(t) => {
+                t.set_z_index(z_index);
+            }
         }
     }
 
@@ -120,6 +233,9 @@ impl floem_renderer::Renderer for Renderer {
             Renderer::Vger(r) => {
                 r.finish();
             }
+            Renderer::TinySkia(r) => {
+                r.finish();
+            }
         }
     }
 }
@@ -0,0 +1,289 @@
+//! # Snapshot
+//! Golden-image testing for views.
+//!
+//! A [`Snapshot`] renders a view off-screen and compares the result to a reference PNG stored
+//! in its directory. When the images don't match, the rendered image and a diff image are
+//! written next to the reference, so the change can be inspected:
+//! ```rust,ignore
+//! Snapshot::new("snapshots")
+//!     .size((200.0, 50.0))
+//!     .tolerance(2)
+//!     .assert("ellipsis", || label(|| "Some long text".to_string()).style(|| Style::BASE.text_ellipsis()));
+//! ```
+//! A missing reference is an error, with the rendered image written next to where it should
+//! be. Set the `FLOEM_UPDATE_SNAPSHOTS` environment variable to create missing references and
+//! overwrite existing ones instead of comparing against them.
+
+use std::path::{Path, PathBuf};
+
+use floem_renderer::tiny_skia::{Color, Pixmap};
+use glazier::kurbo::Size;
+
+use crate::{app::render_to_image, view::View};
+
+const UPDATE_SNAPSHOTS_ENV: &str = "FLOEM_UPDATE_SNAPSHOTS";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The view couldn't be rendered at the requested size
+    Render,
+    Io(std::io::Error),
+    Png(String),
+    /// There's no reference image to compare to
+    MissingReference {
+        reference_path: PathBuf,
+        actual_path: PathBuf,
+    },
+    /// The rendered image has a different size than the reference
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+        actual_path: PathBuf,
+    },
+    /// Some pixels differ from the reference by more than the tolerance
+    Mismatch {
+        mismatched: usize,
+        actual_path: PathBuf,
+        diff_path: PathBuf,
+    },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Render => write!(f, "failed to render the view"),
+            SnapshotError::Io(e) => write!(f, "{e}"),
+            SnapshotError::Png(e) => write!(f, "{e}"),
+            SnapshotError::MissingReference {
+                reference_path,
+                actual_path,
+            } => write!(
+                f,
+                "there's no reference image at {}, see {} and set {UPDATE_SNAPSHOTS_ENV} to accept it",
+                reference_path.display(),
+                actual_path.display()
+            ),
+            SnapshotError::SizeMismatch {
+                expected,
+                actual,
+                actual_path,
+            } => write!(
+                f,
+                "expected a {}x{} image but rendered {}x{}, see {}",
+                expected.0,
+                expected.1,
+                actual.0,
+                actual.1,
+                actual_path.display()
+            ),
+            SnapshotError::Mismatch {
+                mismatched,
+                actual_path,
+                diff_path,
+            } => write!(
+                f,
+                "{mismatched} pixels differ from the reference, see {} and {}",
+                actual_path.display(),
+                diff_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+pub struct Snapshot {
+    dir: PathBuf,
+    size: Size,
+    scale: f64,
+    tolerance: u8,
+}
+
+impl Snapshot {
+    /// Creates a snapshot that keeps its reference images in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            size: Size::new(800.0, 600.0),
+            scale: 1.0,
+            tolerance: 0,
+        }
+    }
+
+    /// The logical size the view is rendered at
+    pub fn size(mut self, size: impl Into<Size>) -> Self {
+        self.size = size.into();
+        self
+    }
+
+    /// The scale factor the view is rendered at
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// The largest difference allowed in any color channel of a pixel for it to still match
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Renders the view and compares it to the reference image called `name`
+    pub fn check<V: View + 'static>(
+        &self,
+        name: &str,
+        app_view: impl FnOnce() -> V,
+    ) -> Result<(), SnapshotError> {
+        let actual =
+            render_to_image(app_view, self.size, self.scale).ok_or(SnapshotError::Render)?;
+
+        let reference_path = self.path(name, "png");
+        let actual_path = self.path(name, "new.png");
+        let diff_path = self.path(name, "diff.png");
+
+        if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+            std::fs::create_dir_all(&self.dir)?;
+            save_png(&actual, &reference_path)?;
+            let _ = std::fs::remove_file(&actual_path);
+            let _ = std::fs::remove_file(&diff_path);
+            return Ok(());
+        }
+
+        if !reference_path.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            save_png(&actual, &actual_path)?;
+            return Err(SnapshotError::MissingReference {
+                reference_path,
+                actual_path,
+            });
+        }
+
+        let reference =
+            Pixmap::load_png(&reference_path).map_err(|e| SnapshotError::Png(e.to_string()))?;
+        if (reference.width(), reference.height()) != (actual.width(), actual.height()) {
+            save_png(&actual, &actual_path)?;
+            return Err(SnapshotError::SizeMismatch {
+                expected: (reference.width(), reference.height()),
+                actual: (actual.width(), actual.height()),
+                actual_path,
+            });
+        }
+
+        let (mismatched, diff) = compare(&reference, &actual, self.tolerance);
+        if mismatched > 0 {
+            save_png(&actual, &actual_path)?;
+            save_png(&diff, &diff_path)?;
+            return Err(SnapshotError::Mismatch {
+                mismatched,
+                actual_path,
+                diff_path,
+            });
+        }
+
+        let _ = std::fs::remove_file(&actual_path);
+        let _ = std::fs::remove_file(&diff_path);
+        Ok(())
+    }
+
+    /// Like [`Snapshot::check`], but panics if the view doesn't match the reference image
+    pub fn assert<V: View + 'static>(&self, name: &str, app_view: impl FnOnce() -> V) {
+        if let Err(e) = self.check(name, app_view) {
+            panic!("snapshot `{name}` failed: {e}");
+        }
+    }
+
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{extension}"))
+    }
+}
+
+/// Compares two images of the same size, returning the number of pixels with a channel
+/// that differs by more than `tolerance`, and an image highlighting those pixels in red
+/// over a faded copy of the reference.
+pub fn compare(reference: &Pixmap, actual: &Pixmap, tolerance: u8) -> (usize, Pixmap) {
+    let mut diff = Pixmap::new(reference.width(), reference.height()).unwrap();
+    diff.fill(Color::WHITE);
+
+    let mut mismatched = 0;
+    for ((expected, actual), diff) in reference
+        .pixels()
+        .iter()
+        .zip(actual.pixels().iter())
+        .zip(diff.data_mut().chunks_exact_mut(4))
+    {
+        let expected = expected.demultiply();
+        let actual = actual.demultiply();
+        let channels = [
+            (expected.red(), actual.red()),
+            (expected.green(), actual.green()),
+            (expected.blue(), actual.blue()),
+            (expected.alpha(), actual.alpha()),
+        ];
+        if channels.iter().any(|(e, a)| e.abs_diff(*a) > tolerance) {
+            mismatched += 1;
+            diff.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma =
+                (expected.red() as u16 + expected.green() as u16 + expected.blue() as u16) / 3;
+            // fade towards white so the mismatched pixels stand out
+            let faded = (255 - (255 - luma) / 4) as u8;
+            diff.copy_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (mismatched, diff)
+}
+
+fn save_png(image: &Pixmap, path: &Path) -> Result<(), SnapshotError> {
+    image
+        .save_png(path)
+        .map_err(|e| SnapshotError::Png(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use floem_renderer::tiny_skia::{Color, Pixmap};
+
+    use super::compare;
+
+    fn image(color: Color) -> Pixmap {
+        let mut image = Pixmap::new(4, 4).unwrap();
+        image.fill(color);
+        image
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let reference = image(Color::from_rgba8(10, 20, 30, 255));
+        let (mismatched, _) = compare(&reference, &reference.clone(), 0);
+        assert_eq!(mismatched, 0);
+    }
+
+    #[test]
+    fn differences_within_tolerance_match() {
+        let reference = image(Color::from_rgba8(10, 20, 30, 255));
+        let actual = image(Color::from_rgba8(12, 18, 30, 255));
+        assert_eq!(compare(&reference, &actual, 2).0, 0);
+        assert_eq!(compare(&reference, &actual, 1).0, 16);
+    }
+
+    #[test]
+    fn diff_marks_mismatched_pixels() {
+        let reference = image(Color::WHITE);
+        let mut actual = image(Color::WHITE);
+        actual.pixels_mut()[5] = Color::BLACK.premultiply().to_color_u8();
+
+        let (mismatched, diff) = compare(&reference, &actual, 0);
+        assert_eq!(mismatched, 1);
+        let pixel = diff.pixel(1, 1).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (255, 0, 0));
+        let pixel = diff.pixel(0, 0).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (255, 255, 255));
+    }
+}
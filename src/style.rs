@@ -26,15 +26,23 @@
 //!
 
 use floem_renderer::cosmic_text::{LineHeightValue, Style as FontStyle, Weight};
-pub use taffy::style::{
-    AlignContent, AlignItems, Dimension, Display, FlexDirection, JustifyContent, Position,
+use glazier::kurbo::{Affine, RoundedRectRadii, Size, Vec2};
+pub use taffy::{
+    geometry::Line,
+    style::{
+        AlignContent, AlignItems, AlignSelf, Dimension, Display, FlexDirection, FlexWrap,
+        GridAutoFlow, GridPlacement, GridTrackRepetition, JustifyContent, JustifySelf,
+        MaxTrackSizingFunction, MinTrackSizingFunction, NonRepeatedTrackSizingFunction, Position,
+        TrackSizingFunction,
+    },
+    style_helpers,
 };
 use taffy::{
     prelude::Rect,
     style::{LengthPercentage, LengthPercentageAuto, Style as TaffyStyle},
     style_helpers::TaffyZero,
 };
-use vello::peniko::Color;
+use vello::peniko::{Cap, Color, Join, Stroke};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextOverflow {
@@ -50,6 +58,56 @@ pub enum CursorStyle {
     Text,
 }
 
+/// How the border of a view is drawn, like CSS's `border-style`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderStyle {
+    Solid,
+    /// Dashes three times as long as the border is wide, with gaps as long
+    Dashed,
+    /// Square dots as wide as the border, with gaps as wide
+    Dotted,
+}
+
+impl BorderStyle {
+    /// The stroke for a border of `width`, which is drawn along the middle of the border
+    pub fn stroke(self, width: f32) -> Stroke {
+        let stroke = Stroke::new(width)
+            .with_join(Join::Miter)
+            .with_caps(Cap::Butt);
+        match self {
+            BorderStyle::Solid => stroke,
+            BorderStyle::Dashed => stroke.with_dashes(0.0, [width * 3.0, width * 3.0]),
+            BorderStyle::Dotted => stroke.with_dashes(0.0, [width, width]),
+        }
+    }
+}
+
+/// A shadow drawn under a view, like CSS's `box-shadow`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    /// How far the shadow is moved to the right
+    pub h_offset: f64,
+    /// How far the shadow is moved down
+    pub v_offset: f64,
+    /// How much the edges of the shadow are blurred, 0 keeps them sharp
+    pub blur_radius: f64,
+    /// How much the shadow grows past the view on every side, or shrinks when negative
+    pub spread: f64,
+    pub color: Color,
+}
+
+impl Default for BoxShadow {
+    fn default() -> Self {
+        Self {
+            h_offset: 0.0,
+            v_offset: 0.0,
+            blur_radius: 0.0,
+            spread: 0.0,
+            color: Color::BLACK,
+        }
+    }
+}
+
 /// The value for a [`Style`] property
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StyleValue<T> {
@@ -216,18 +274,44 @@ define_styles!(
     max_width: Dimension = Dimension::Auto,
     max_height: Dimension = Dimension::Auto,
     flex_direction: FlexDirection = FlexDirection::Row,
+    flex_wrap: FlexWrap = FlexWrap::NoWrap,
     flex_grow: f32 = 0.0,
     flex_shrink: f32 = 1.0,
     flex_basis: Dimension = Dimension::Auto,
     justify_content: Option<JustifyContent> = None,
     align_items: Option<AlignItems> = None,
     align_content: Option<AlignContent> = None,
+    align_self: Option<AlignSelf> = None,
+    justify_self: Option<JustifySelf> = None,
+    aspect_ratio nocb: Option<f32> = None,
+    grid_template_rows: Vec<TrackSizingFunction> = Vec::new(),
+    grid_template_columns: Vec<TrackSizingFunction> = Vec::new(),
+    grid_auto_rows: Vec<NonRepeatedTrackSizingFunction> = Vec::new(),
+    grid_auto_columns: Vec<NonRepeatedTrackSizingFunction> = Vec::new(),
+    grid_auto_flow: GridAutoFlow = GridAutoFlow::Row,
+    grid_row: Line<GridPlacement> = Line {
+        start: GridPlacement::Auto,
+        end: GridPlacement::Auto,
+    },
+    grid_column: Line<GridPlacement> = Line {
+        start: GridPlacement::Auto,
+        end: GridPlacement::Auto,
+    },
+    row_gap: LengthPercentage = LengthPercentage::ZERO,
+    column_gap: LengthPercentage = LengthPercentage::ZERO,
     border_left: f32 = 0.0,
     border_top: f32 = 0.0,
     border_right: f32 = 0.0,
     border_bottom: f32 = 0.0,
-    border_radius: f32 = 0.0,
-    border_color: Color = Color::BLACK,
+    border_top_left_radius: f32 = 0.0,
+    border_top_right_radius: f32 = 0.0,
+    border_bottom_right_radius: f32 = 0.0,
+    border_bottom_left_radius: f32 = 0.0,
+    border_left_color: Color = Color::BLACK,
+    border_top_color: Color = Color::BLACK,
+    border_right_color: Color = Color::BLACK,
+    border_bottom_color: Color = Color::BLACK,
+    border_style: BorderStyle = BorderStyle::Solid,
     padding_left: LengthPercentage = LengthPercentage::ZERO,
     padding_top: LengthPercentage = LengthPercentage::ZERO,
     padding_right: LengthPercentage = LengthPercentage::ZERO,
@@ -244,6 +328,14 @@ define_styles!(
     cursor nocb: Option<CursorStyle> = None,
     color nocb: Option<Color> = None,
     background nocb: Option<Color> = None,
+    box_shadow nocb: Option<BoxShadow> = None,
+    opacity: f32 = 1.0,
+    translate_x: f64 = 0.0,
+    translate_y: f64 = 0.0,
+    rotate: f64 = 0.0,
+    scale_x: f64 = 1.0,
+    scale_y: f64 = 1.0,
+    transform_origin nocb: (f64, f64) = (0.5, 0.5),
     font_size nocb: Option<f32> = None,
     font_family nocb: Option<String> = None,
     font_weight nocb: Option<Weight> = None,
@@ -367,6 +459,46 @@ impl Style {
         self.border_top(border).border_bottom(border)
     }
 
+    /// Sets the radius of all four corners to `radius`
+    pub fn border_radius(self, radius: f32) -> Self {
+        self.border_top_left_radius(radius)
+            .border_top_right_radius(radius)
+            .border_bottom_right_radius(radius)
+            .border_bottom_left_radius(radius)
+    }
+
+    /// Sets the radius of the two top corners to `radius`
+    pub fn border_top_radius(self, radius: f32) -> Self {
+        self.border_top_left_radius(radius)
+            .border_top_right_radius(radius)
+    }
+
+    /// Sets the radius of the two bottom corners to `radius`
+    pub fn border_bottom_radius(self, radius: f32) -> Self {
+        self.border_bottom_left_radius(radius)
+            .border_bottom_right_radius(radius)
+    }
+
+    /// Sets the radius of the two left corners to `radius`
+    pub fn border_left_radius(self, radius: f32) -> Self {
+        self.border_top_left_radius(radius)
+            .border_bottom_left_radius(radius)
+    }
+
+    /// Sets the radius of the two right corners to `radius`
+    pub fn border_right_radius(self, radius: f32) -> Self {
+        self.border_top_right_radius(radius)
+            .border_bottom_right_radius(radius)
+    }
+
+    /// Sets the color of all four sides to `color`
+    pub fn border_color(self, color: Color) -> Self {
+        self.border_left_color(color)
+            .border_top_color(color)
+            .border_right_color(color)
+            .border_bottom_color(color)
+    }
+
     pub fn padding_left_px(self, padding: f32) -> Self {
         self.padding_left(LengthPercentage::Points(padding))
     }
@@ -557,6 +689,42 @@ impl Style {
         self
     }
 
+    pub fn box_shadow(mut self, shadow: impl Into<StyleValue<BoxShadow>>) -> Self {
+        self.box_shadow = shadow.into().map(Some);
+        self
+    }
+
+    /// A shadow of `color` below the view, blurred by `blur_radius`, for elevation
+    pub fn box_shadow_blur(self, blur_radius: f64, color: Color) -> Self {
+        self.box_shadow(BoxShadow {
+            v_offset: blur_radius / 2.0,
+            blur_radius,
+            color,
+            ..Default::default()
+        })
+    }
+
+    pub fn translate(self, x: f64, y: f64) -> Self {
+        self.translate_x(x).translate_y(y)
+    }
+
+    /// Scales the view by `scale` in both directions around its transform origin
+    pub fn scale(self, scale: f64) -> Self {
+        self.scale_x(scale).scale_y(scale)
+    }
+
+    /// Rotates the view clockwise by `degrees` around its transform origin
+    pub fn rotate_deg(self, degrees: f64) -> Self {
+        self.rotate(degrees.to_radians())
+    }
+
+    /// The point the view is rotated and scaled around, as fractions of its size.
+    /// Defaults to the center, `(0.5, 0.5)`.
+    pub fn transform_origin(mut self, x: f64, y: f64) -> Self {
+        self.transform_origin = StyleValue::Val((x, y));
+        self
+    }
+
     pub fn font_size(mut self, size: impl Into<StyleValue<f32>>) -> Self {
         self.font_size = size.into().map(Some);
         self
@@ -603,6 +771,25 @@ impl Style {
         self.position(Position::Absolute)
     }
 
+    /// Keeps the view in the flow, and moves it by its insets without moving
+    /// its siblings
+    pub fn relative(self) -> Self {
+        self.position(Position::Relative)
+    }
+
+    /// Moves the view right by `x` and down by `y` from where the layout puts it,
+    /// like a relatively positioned view with `inset_left` and `inset_top`
+    pub fn offset_px(self, x: f32, y: f32) -> Self {
+        self.relative().inset_left_px(x).inset_top_px(y)
+    }
+
+    /// Sets the width divided by the height, which sizes the view when only
+    /// one of them is known
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = Some(ratio).into();
+        self
+    }
+
     pub fn items_start(self) -> Self {
         self.align_items(Some(AlignItems::FlexStart))
     }
@@ -633,6 +820,23 @@ impl Style {
         self.justify_content(Some(JustifyContent::SpaceBetween))
     }
 
+    /// Aligns the view itself along the cross axis, overriding the `align_items` of its parent
+    pub fn self_start(self) -> Self {
+        self.align_self(Some(AlignSelf::FlexStart))
+    }
+
+    pub fn self_center(self) -> Self {
+        self.align_self(Some(AlignSelf::Center))
+    }
+
+    pub fn self_end(self) -> Self {
+        self.align_self(Some(AlignSelf::FlexEnd))
+    }
+
+    pub fn self_stretch(self) -> Self {
+        self.align_self(Some(AlignSelf::Stretch))
+    }
+
     pub fn hide(self) -> Self {
         self.display(Display::None)
     }
@@ -654,6 +858,88 @@ impl Style {
         self.flex_direction(FlexDirection::Column)
     }
 
+    /// Moves the children that don't fit onto new lines, like `flex-wrap: wrap`
+    pub fn wrap(self) -> Self {
+        self.flex_wrap(FlexWrap::Wrap)
+    }
+
+    /// Lays the children out in a grid, with the tracks of `grid_template_rows`
+    /// and `grid_template_columns`:
+    /// ```rust,ignore
+    /// use floem::style::style_helpers::{fr, minmax, points, repeat};
+    ///
+    /// Style::BASE
+    ///     .grid()
+    ///     .grid_template_columns(vec![
+    ///         points(200.0),
+    ///         repeat(GridTrackRepetition::Count(3), vec![minmax(points(100.0), fr(1.0))]),
+    ///     ])
+    ///     .gap_px(8.0)
+    /// ```
+    pub fn grid(self) -> Self {
+        self.display(Display::Grid)
+    }
+
+    pub fn row_gap_px(self, gap: f32) -> Self {
+        self.row_gap(LengthPercentage::Points(gap))
+    }
+
+    pub fn row_gap_pct(self, gap: f32) -> Self {
+        self.row_gap(LengthPercentage::Percent(gap / 100.0))
+    }
+
+    pub fn column_gap_px(self, gap: f32) -> Self {
+        self.column_gap(LengthPercentage::Points(gap))
+    }
+
+    pub fn column_gap_pct(self, gap: f32) -> Self {
+        self.column_gap(LengthPercentage::Percent(gap / 100.0))
+    }
+
+    /// Sets `row_gap` and `column_gap` to `gap`
+    pub fn gap_px(self, gap: f32) -> Self {
+        self.row_gap_px(gap).column_gap_px(gap)
+    }
+
+    /// Sets `row_gap` and `column_gap` to `gap`
+    pub fn gap_pct(self, gap: f32) -> Self {
+        self.row_gap_pct(gap).column_gap_pct(gap)
+    }
+
+    /// Places the view in the grid from line `start` to line `end` of the rows,
+    /// where negative lines count from the last one
+    pub fn grid_row_lines(self, start: i16, end: i16) -> Self {
+        self.grid_row(Line {
+            start: line(start),
+            end: line(end),
+        })
+    }
+
+    /// Places the view in the grid from line `start` to line `end` of the columns,
+    /// where negative lines count from the last one
+    pub fn grid_column_lines(self, start: i16, end: i16) -> Self {
+        self.grid_column(Line {
+            start: line(start),
+            end: line(end),
+        })
+    }
+
+    /// Makes the view span `rows` rows of the grid, wherever it's placed
+    pub fn grid_row_span(self, rows: u16) -> Self {
+        self.grid_row(Line {
+            start: span(rows),
+            end: GridPlacement::Auto,
+        })
+    }
+
+    /// Makes the view span `columns` columns of the grid, wherever it's placed
+    pub fn grid_column_span(self, columns: u16) -> Self {
+        self.grid_column(Line {
+            start: span(columns),
+            end: GridPlacement::Auto,
+        })
+    }
+
     /// Allow the application of a function if the option exists.  
     /// This is useful for chaining together a bunch of optional style changes.  
     /// ```rust,ignore
@@ -688,6 +974,37 @@ impl Style {
 }
 
 impl ComputedStyle {
+    /// The radii of the corners, for a [`RoundedRect`](glazier::kurbo::RoundedRect)
+    pub fn border_radii(&self) -> RoundedRectRadii {
+        RoundedRectRadii::new(
+            self.border_top_left_radius as f64,
+            self.border_top_right_radius as f64,
+            self.border_bottom_right_radius as f64,
+            self.border_bottom_left_radius as f64,
+        )
+    }
+
+    /// The translation, rotation (in radians) and scale of a view of `size`,
+    /// relative to its layout location
+    pub fn transform(&self, size: Size) -> Affine {
+        if self.translate_x == 0.0
+            && self.translate_y == 0.0
+            && self.rotate == 0.0
+            && self.scale_x == 1.0
+            && self.scale_y == 1.0
+        {
+            return Affine::IDENTITY;
+        }
+        let origin = Vec2::new(
+            size.width * self.transform_origin.0,
+            size.height * self.transform_origin.1,
+        );
+        Affine::translate(origin + Vec2::new(self.translate_x, self.translate_y))
+            * Affine::rotate(self.rotate)
+            * Affine::scale_non_uniform(self.scale_x, self.scale_y)
+            * Affine::translate(-origin)
+    }
+
     pub fn to_taffy_style(&self) -> TaffyStyle {
         TaffyStyle {
             display: self.display,
@@ -705,12 +1022,27 @@ impl ComputedStyle {
                 height: self.max_height,
             },
             flex_direction: self.flex_direction,
+            flex_wrap: self.flex_wrap,
             flex_grow: self.flex_grow,
             flex_shrink: self.flex_shrink,
             flex_basis: self.flex_basis,
             justify_content: self.justify_content,
             align_items: self.align_items,
             align_content: self.align_content,
+            align_self: self.align_self,
+            justify_self: self.justify_self,
+            aspect_ratio: self.aspect_ratio,
+            gap: taffy::prelude::Size {
+                width: self.column_gap,
+                height: self.row_gap,
+            },
+            grid_template_rows: self.grid_template_rows.clone(),
+            grid_template_columns: self.grid_template_columns.clone(),
+            grid_auto_rows: self.grid_auto_rows.clone(),
+            grid_auto_columns: self.grid_auto_columns.clone(),
+            grid_auto_flow: self.grid_auto_flow,
+            grid_row: self.grid_row,
+            grid_column: self.grid_column,
             border: Rect {
                 left: LengthPercentage::Points(self.border_left),
                 top: LengthPercentage::Points(self.border_top),
@@ -742,9 +1074,18 @@ impl ComputedStyle {
 
 #[cfg(test)]
 mod tests {
+    use glazier::kurbo::{Point, Size};
     use taffy::style::LengthPercentage;
 
-    use super::{Style, StyleValue};
+    use crate::{
+        test_harness::{TestHarness, ViewIds},
+        views::{empty, stack, Decorators},
+    };
+
+    use super::{
+        style_helpers::{fr, points},
+        AlignContent, ComputedStyle, Style, StyleValue,
+    };
 
     #[test]
     fn style_override() {
@@ -818,4 +1159,98 @@ mod tests {
             StyleValue::Val(LengthPercentage::Points(100.0))
         );
     }
+
+    #[test]
+    fn transform_is_around_the_origin() {
+        let size = Size::new(100.0, 50.0);
+        let style = Style::BASE.scale(2.0).compute(&ComputedStyle::default());
+        let transform = style.transform(size);
+        // scaled around the center, which stays where it is
+        assert_eq!(transform * Point::new(50.0, 25.0), Point::new(50.0, 25.0));
+        assert_eq!(transform * Point::new(0.0, 0.0), Point::new(-50.0, -25.0));
+
+        let style = Style::BASE
+            .translate(10.0, 0.0)
+            .rotate_deg(90.0)
+            .transform_origin(0.0, 0.0)
+            .compute(&ComputedStyle::default());
+        let point = style.transform(size) * Point::new(10.0, 0.0);
+        assert!((point - Point::new(10.0, 10.0)).hypot() < 1e-9);
+    }
+
+    #[test]
+    fn border_radius_sets_every_corner() {
+        let style = Style::BASE
+            .border_radius(4.0)
+            .border_top_right_radius(0.0)
+            .compute(&ComputedStyle::default());
+        let radii = style.border_radii();
+        assert_eq!(
+            (
+                radii.top_left,
+                radii.top_right,
+                radii.bottom_right,
+                radii.bottom_left
+            ),
+            (4.0, 0.0, 4.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn grid_places_children_in_tracks() {
+        let views = ViewIds::default();
+        let grid_views = views.clone();
+        let harness = TestHarness::new(
+            move || {
+                let cell = || grid_views.record(empty().style(|| Style::BASE.height_px(20.0)));
+                let (first, second) = (cell(), cell());
+                let wide = grid_views
+                    .record(empty().style(|| Style::BASE.height_px(20.0).grid_column_span(2)));
+                stack(move || (first, second, wide)).style(|| {
+                    Style::BASE
+                        .size_px(200.0, 100.0)
+                        .grid()
+                        .grid_template_columns(vec![points(50.0), fr(1.0)])
+                        .gap_px(10.0)
+                })
+            },
+            (200.0, 100.0),
+        );
+        let (first, second, wide) = (views.get(0), views.get(1), views.get(2));
+
+        assert_eq!(harness.layout_rect(first).unwrap().width(), 50.0);
+        let second = harness.layout_rect(second).unwrap();
+        assert_eq!((second.x0, second.width()), (60.0, 140.0));
+        let wide = harness.layout_rect(wide).unwrap();
+        assert_eq!((wide.x0, wide.y0, wide.width()), (0.0, 30.0, 200.0));
+    }
+
+    #[test]
+    fn wrapped_children_keep_gaps_and_aspect_ratio() {
+        let views = ViewIds::default();
+        let wrap_views = views.clone();
+        let harness = TestHarness::new(
+            move || {
+                let square = empty().style(|| Style::BASE.size_px(40.0, 40.0));
+                let other = wrap_views.record(empty().style(|| Style::BASE.size_px(40.0, 40.0)));
+                let wide = wrap_views
+                    .record(empty().style(|| Style::BASE.width_px(40.0).aspect_ratio(2.0)));
+                stack(move || (square, other, wide)).style(|| {
+                    Style::BASE
+                        .width_px(100.0)
+                        .wrap()
+                        .gap_px(10.0)
+                        .items_start()
+                        .align_content(Some(AlignContent::FlexStart))
+                })
+            },
+            (200.0, 200.0),
+        );
+        let (other, wide) = (views.get(0), views.get(1));
+
+        assert_eq!(harness.layout_rect(other).unwrap().x0, 50.0);
+        let wide = harness.layout_rect(wide).unwrap();
+        assert_eq!((wide.x0, wide.y0), (0.0, 50.0));
+        assert_eq!(wide.size(), Size::new(40.0, 20.0));
+    }
 }
@@ -0,0 +1,318 @@
+//! # Test harness
+//! A [`TestHarness`] owns an app without a real window, so tests can drive it with
+//! synthetic input and inspect the resulting state:
+//! ```rust,ignore
+//! let mut harness = TestHarness::new(app_view, (800.0, 600.0));
+//! harness.click((10.0, 10.0));
+//! harness.advance_time(Duration::from_millis(500));
+//! assert_eq!(harness.focus(), Some(button_id));
+//! ```
+//! Timers requested with [`Id::exec_after`], and animations, run on a virtual clock
+//! that only moves forward with [`TestHarness::advance_time`].
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use accesskit::TreeUpdate;
+use floem_renderer::tiny_skia::Pixmap;
+use glazier::{
+    kurbo::{Point, Rect, Size},
+    KbKey, KeyEvent, KeyState, Modifiers, PointerButton, PointerEvent, WinHandler,
+};
+use leptos_reactive::{create_runtime, raw_scope_and_disposer, RuntimeId, Scope};
+
+use crate::{app_handle::AppHandle, event::Event, id::Id, view::View};
+
+/// Records the ids of views as the app view is built, so a test can find them
+/// once the harness has built it:
+/// ```rust,ignore
+/// let ids = ViewIds::default();
+/// let view_ids = ids.clone();
+/// let harness = TestHarness::new(move || view_ids.record(label(|| "a")), (100.0, 100.0));
+/// let label = ids.get(0);
+/// ```
+#[derive(Clone, Default)]
+pub struct ViewIds(Rc<RefCell<Vec<Id>>>);
+
+impl ViewIds {
+    /// Records the id of `view`, and passes it on
+    pub fn record<V: View>(&self, view: V) -> V {
+        self.0.borrow_mut().push(view.id());
+        view
+    }
+
+    /// The id recorded `index`th
+    pub fn get(&self, index: usize) -> Id {
+        self.0.borrow()[index]
+    }
+}
+
+pub struct TestHarness<V: View> {
+    runtime: RuntimeId,
+    scope: Scope,
+    app: Option<AppHandle<V>>,
+    size: Size,
+}
+
+impl<V: View> TestHarness<V> {
+    /// Builds the view and lays it out at the given window size
+    pub fn new(app_view: impl FnOnce() -> V, size: impl Into<Size>) -> Self {
+        let size = size.into();
+        let runtime = create_runtime();
+        let (scope, _) = raw_scope_and_disposer(runtime);
+        let mut app = AppHandle::new(scope, app_view);
+        app.connect_headless(size);
+        Self {
+            runtime,
+            scope,
+            app: Some(app),
+            size,
+        }
+    }
+
+    pub(crate) fn app(&self) -> &AppHandle<V> {
+        self.app.as_ref().unwrap()
+    }
+
+    fn app_mut(&mut self) -> &mut AppHandle<V> {
+        self.app.as_mut().unwrap()
+    }
+
+    /// The reactive scope the view was built in
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// The id of the root view
+    pub fn root_id(&self) -> Id {
+        self.app().view_id()
+    }
+
+    /// Dispatches an event as if it came from the window
+    pub fn event(&mut self, event: Event) {
+        self.app_mut().event(event);
+    }
+
+    pub fn pointer_down(&mut self, pos: impl Into<Point>) {
+        self.event(Event::PointerDown(pointer_event(pos.into())));
+    }
+
+    pub fn pointer_up(&mut self, pos: impl Into<Point>) {
+        self.event(Event::PointerUp(pointer_event(pos.into())));
+    }
+
+    pub fn pointer_move(&mut self, pos: impl Into<Point>) {
+        let mut event = pointer_event(pos.into());
+        event.button = PointerButton::None;
+        event.count = 0;
+        self.event(Event::PointerMove(event));
+    }
+
+    /// A pointer down followed by a pointer up at the same position
+    pub fn click(&mut self, pos: impl Into<Point>) {
+        let pos = pos.into();
+        self.pointer_down(pos);
+        self.pointer_up(pos);
+    }
+
+    pub fn key_down(&mut self, key: KbKey, mods: Modifiers) {
+        self.event(Event::KeyDown(KeyEvent {
+            state: KeyState::Down,
+            key,
+            mods,
+            ..Default::default()
+        }));
+    }
+
+    pub fn key_up(&mut self, key: KbKey, mods: Modifiers) {
+        self.event(Event::KeyUp(KeyEvent {
+            state: KeyState::Up,
+            key,
+            mods,
+            ..Default::default()
+        }));
+    }
+
+    /// Resizes the window, sending `Event::WindowResized` and laying out again
+    pub fn resize(&mut self, size: impl Into<Size>) {
+        self.size = size.into();
+        let size = self.size;
+        self.app_mut().size(size);
+    }
+
+    /// Processes any pending updates, such as style changes from signals set by the test
+    pub fn update(&mut self) {
+        self.app_mut().process_update();
+    }
+
+    /// The time elapsed on the virtual clock
+    pub fn now(&self) -> Duration {
+        self.app()
+            .app_state()
+            .virtual_clock
+            .as_ref()
+            .map(|clock| clock.now)
+            .unwrap_or_default()
+    }
+
+    /// Moves the virtual clock forward, running every timer that becomes due
+    /// in the order of its deadline
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.update();
+        let until = self.now() + duration;
+        loop {
+            let token = self
+                .app_mut()
+                .app_state_mut()
+                .virtual_clock
+                .as_mut()
+                .and_then(|clock| clock.next_due(until));
+            match token {
+                Some(token) => self.app_mut().timer(token),
+                None => break,
+            }
+        }
+        if let Some(clock) = self.app_mut().app_state_mut().virtual_clock.as_mut() {
+            clock.now = until;
+        }
+    }
+
+    /// The view with keyboard focus
+    pub fn focus(&self) -> Option<Id> {
+        self.app().app_state().focus
+    }
+
+    /// The view that currently receives all pointer events
+    pub fn active(&self) -> Option<Id> {
+        self.app().app_state().active
+    }
+
+    pub fn is_hovered(&self, id: Id) -> bool {
+        self.app().app_state().is_hovered(&id)
+    }
+
+    pub fn is_focused(&self, id: Id) -> bool {
+        self.app().app_state().is_focused(&id)
+    }
+
+    pub fn is_active(&self, id: Id) -> bool {
+        self.app().app_state().is_active(&id)
+    }
+
+    pub fn is_disabled(&self, id: Id) -> bool {
+        self.app().app_state().is_disabled(&id)
+    }
+
+    /// The layout of the view in window coordinates, if it has been laid out
+    pub fn layout_rect(&self, id: Id) -> Option<Rect> {
+        self.app()
+            .app_state()
+            .view_states
+            .get(&id)
+            .map(|view| view.layout_rect)
+    }
+
+    /// The size of the view computed by the last layout
+    pub fn size_of(&self, id: Id) -> Option<Size> {
+        self.app()
+            .app_state()
+            .get_layout(id)
+            .map(|layout| Size::new(layout.size.width as f64, layout.size.height as f64))
+    }
+
+    /// The accessibility tree that would be sent to assistive technologies
+    pub fn accessibility_tree(&mut self) -> TreeUpdate {
+        self.app_mut().accessibility_tree()
+    }
+
+    /// Paints the view off-screen at the current window size
+    pub fn render(&mut self, scale: f64) -> Option<Pixmap> {
+        let size = self.size;
+        self.app_mut().render_to_image(size, scale)
+    }
+}
+
+impl<V: View> Drop for TestHarness<V> {
+    fn drop(&mut self) {
+        // the app disposes its scope, which has to happen before the runtime goes away
+        self.app.take();
+        self.runtime.dispose();
+    }
+}
+
+fn pointer_event(pos: Point) -> PointerEvent {
+    PointerEvent {
+        pos,
+        button: PointerButton::Left,
+        count: 1,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use glazier::kurbo::Size;
+
+    use crate::{
+        style::Style,
+        views::{empty, stack, Decorators},
+    };
+
+    use super::TestHarness;
+
+    #[test]
+    fn click_focuses_and_runs_listener() {
+        let clicks = Rc::new(Cell::new(0));
+        let button_clicks = clicks.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                stack(move || {
+                    (empty()
+                        .style(|| Style::BASE.size_px(50.0, 50.0))
+                        .keyboard_navigatable()
+                        .on_click(move |_| {
+                            button_clicks.set(button_clicks.get() + 1);
+                            true
+                        }),)
+                })
+            },
+            (200.0, 200.0),
+        );
+        let button = harness.root_id().nested_last_child();
+
+        harness.pointer_move((10.0, 10.0));
+        assert!(harness.is_hovered(button));
+
+        harness.pointer_down((10.0, 10.0));
+        assert!(harness.is_active(button));
+        harness.pointer_up((10.0, 10.0));
+        assert_eq!(clicks.get(), 1);
+        assert_eq!(harness.focus(), Some(button));
+        assert_eq!(harness.active(), None);
+
+        harness.click((100.0, 100.0));
+        assert_eq!(clicks.get(), 1);
+        assert_eq!(harness.focus(), None);
+
+        let rect = harness.layout_rect(button).unwrap();
+        assert_eq!(rect.size(), Size::new(50.0, 50.0));
+    }
+
+    #[test]
+    fn timers_follow_virtual_clock() {
+        let fired = Rc::new(Cell::new(false));
+        let mut harness = TestHarness::new(empty, (100.0, 100.0));
+
+        let timer_fired = fired.clone();
+        harness
+            .root_id()
+            .exec_after(Duration::from_millis(100), move || timer_fired.set(true));
+
+        harness.advance_time(Duration::from_millis(60));
+        assert!(!fired.get());
+        harness.advance_time(Duration::from_millis(60));
+        assert!(fired.get());
+        assert_eq!(harness.now(), Duration::from_millis(120));
+    }
+}
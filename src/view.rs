@@ -1,12 +1,17 @@
 use std::any::Any;
 
+use accesskit::{Action, DefaultActionVerb, NodeBuilder, NodeId};
 use bitflags::bitflags;
 use floem_renderer::Renderer;
-use glazier::kurbo::{Affine, Circle, Line, Point, Rect, Size};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use glazier::kurbo::{
+    Affine, Arc, BezPath, Circle, Line, Point, Rect, RoundedRect, RoundedRectRadii, Size, Vec2,
+};
 use taffy::prelude::Node;
 
 use crate::{
-    context::{DragState, EventCx, LayoutCx, PaintCx, UpdateCx},
+    context::{AccessibilityCx, DragState, EventCx, LayoutCx, PaintCx, UpdateCx},
     event::{Event, EventListener},
     id::Id,
     style::{ComputedStyle, Style},
@@ -160,6 +165,13 @@ pub trait View {
 
         cx.restore();
 
+        // the parent covers the view where it's painted, after its transform
+        let transform = cx.app_state.get_computed_style(self.id()).transform(size);
+        if transform != Affine::IDENTITY {
+            let origin = window_origin.to_vec2();
+            return transform.transform_rect_bbox(layout_rect - origin) + origin;
+        }
+
         layout_rect
     }
 
@@ -428,6 +440,12 @@ pub trait View {
                 cx.set_z_index(z_index);
             }
 
+            let is_faded = style.opacity < 1.0;
+            if is_faded {
+                cx.push_layer(style.opacity);
+            }
+
+            paint_box_shadow(cx, &style, size);
             paint_bg(cx, &style, size);
 
             if style.color.is_some() {
@@ -450,6 +468,10 @@ pub trait View {
             }
             self.paint(cx);
             paint_border(cx, &style, size);
+
+            if is_faded {
+                cx.pop_layer();
+            }
         }
 
         let mut drag_set_to_none = false;
@@ -502,9 +524,17 @@ pub trait View {
                     } else {
                         style
                     };
+                    let is_faded = style.opacity < 1.0;
+                    if is_faded {
+                        cx.push_layer(style.opacity);
+                    }
+                    paint_box_shadow(cx, &style, size);
                     paint_bg(cx, &style, size);
                     self.paint(cx);
                     paint_border(cx, &style, size);
+                    if is_faded {
+                        cx.pop_layer();
+                    }
 
                     cx.restore();
                 }
@@ -519,6 +549,64 @@ pub trait View {
 
     fn paint(&mut self, cx: &mut PaintCx);
 
+    /// Builds the accessibility nodes of this view and its children, returning the
+    /// ids of the nodes that belong directly under the parent's node.
+    fn accessibility_main(&mut self, cx: &mut AccessibilityCx) -> Vec<NodeId> {
+        let id = self.id();
+        if cx.app_state.is_hidden(id) {
+            return Vec::new();
+        }
+
+        let node = self.accessibility(cx);
+        let children: Vec<NodeId> = self
+            .children()
+            .into_iter()
+            .flat_map(|child| child.accessibility_main(cx))
+            .collect();
+
+        let mut node = match node {
+            Some(node) => node,
+            // views without a node are transparent, their children are moved up
+            None => return children,
+        };
+
+        let view_state = cx.app_state.view_state(id);
+        let rect = view_state.layout_rect;
+        let is_clickable = view_state
+            .event_listeners
+            .contains_key(&EventListener::Click);
+        node.set_bounds(accesskit::Rect {
+            x0: rect.x0,
+            y0: rect.y0,
+            x1: rect.x1,
+            y1: rect.y1,
+        });
+        node.set_children(children);
+        if cx.app_state.keyboard_navigatable.contains(&id) {
+            node.add_action(Action::Focus);
+        }
+        if is_clickable {
+            node.add_action(Action::Default);
+            node.set_default_action_verb(DefaultActionVerb::Click);
+        }
+        if cx.app_state.is_disabled(&id) {
+            node.set_disabled();
+        }
+        cx.push_node(id, node);
+
+        vec![id.to_node_id()]
+    }
+
+    /// Describes this view to assistive technologies such as screen readers, with
+    /// its role and, where it has one, its name and value. The bounds, children and
+    /// actions are filled in from the view's layout and listeners.
+    ///
+    /// Views returning `None` are left out of the accessibility tree, and their
+    /// children are attached to the closest ancestor in the tree.
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        None
+    }
+
     /// Produces an ascii art debug display of all of the views.
     fn debug_tree(&mut self)
     where
@@ -579,27 +667,61 @@ pub trait View {
     }
 }
 
+fn paint_box_shadow(cx: &mut PaintCx, style: &ComputedStyle, size: Size) {
+    let shadow = match style.box_shadow {
+        Some(shadow) => shadow,
+        None => return,
+    };
+
+    let rect = size.to_rect().inflate(shadow.spread, shadow.spread)
+        + Vec2::new(shadow.h_offset, shadow.v_offset);
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return;
+    }
+    let spread = |radius: f64| {
+        if radius > 0.0 {
+            (radius + shadow.spread).max(0.0)
+        } else {
+            0.0
+        }
+    };
+    let radii = style.border_radii();
+    let radii = RoundedRectRadii::new(
+        spread(radii.top_left),
+        spread(radii.top_right),
+        spread(radii.bottom_right),
+        spread(radii.bottom_left),
+    );
+    cx.draw_box_shadow(rect, radii, shadow.color, shadow.blur_radius);
+}
+
 fn paint_bg(cx: &mut PaintCx, style: &ComputedStyle, size: Size) {
     let bg = match style.background {
         Some(color) => color,
         None => return,
     };
 
-    let radius = style.border_radius;
-    if radius > 0.0 {
-        let rect = size.to_rect();
-        let width = rect.width();
-        let height = rect.height();
-        if width > 0.0 && height > 0.0 && radius as f64 > width.max(height) / 2.0 {
-            let radius = width.max(height) / 2.0;
-            let circle = Circle::new(rect.center(), radius);
-            cx.fill(&circle, bg);
-        } else {
-            let rect = rect.to_rounded_rect(radius as f64);
-            cx.fill(&rect, bg);
+    let radii = style.border_radii();
+    match radii.as_single_radius() {
+        Some(radius) if radius > 0.0 => {
+            let rect = size.to_rect();
+            let width = rect.width();
+            let height = rect.height();
+            if width > 0.0 && height > 0.0 && radius > width.max(height) / 2.0 {
+                let radius = width.max(height) / 2.0;
+                let circle = Circle::new(rect.center(), radius);
+                cx.fill(&circle, bg);
+            } else {
+                let rect = rect.to_rounded_rect(radius);
+                cx.fill(&rect, bg);
+            }
+        }
+        Some(_) => {
+            cx.fill(&size.to_rect(), bg);
+        }
+        None => {
+            cx.fill(&size.to_rect().to_rounded_rect(radii), bg);
         }
-    } else {
-        cx.fill(&size.to_rect(), bg);
     }
 }
 
@@ -609,27 +731,35 @@ fn paint_border(cx: &mut PaintCx, style: &ComputedStyle, size: Size) {
     let right = style.border_right;
     let bottom = style.border_bottom;
 
-    let border_color = style.border_color;
+    let colors = [
+        style.border_top_color,
+        style.border_right_color,
+        style.border_bottom_color,
+        style.border_left_color,
+    ];
+    let same_color = colors.iter().all(|color| *color == colors[0]);
     if left == top && top == right && right == bottom && bottom == left && left > 0.0 {
         let half = left as f64 / 2.0;
         let rect = size.to_rect().inflate(-half, -half);
-        let radius = style.border_radius;
-        if radius > 0.0 {
-            cx.stroke(
-                &rect.to_rounded_rect(radius as f64),
-                border_color,
-                left as f64,
-            );
+        let radii = style.border_radii();
+        let stroke = style.border_style.stroke(left);
+        if !same_color {
+            let rect = rect.to_rounded_rect(radii);
+            for (side, color) in colors.into_iter().enumerate() {
+                cx.stroke(&border_side(rect, side), color, &stroke);
+            }
+        } else if radii.as_single_radius() == Some(0.0) {
+            cx.stroke(&rect, colors[0], &stroke);
         } else {
-            cx.stroke(&rect, border_color, left as f64);
+            cx.stroke(&rect.to_rounded_rect(radii), colors[0], &stroke);
         }
     } else {
         if left > 0.0 {
             let half = left as f64 / 2.0;
             cx.stroke(
                 &Line::new(Point::new(half, 0.0), Point::new(half, size.height)),
-                border_color,
-                left as f64,
+                style.border_left_color,
+                &style.border_style.stroke(left),
             );
         }
         if right > 0.0 {
@@ -639,16 +769,16 @@ fn paint_border(cx: &mut PaintCx, style: &ComputedStyle, size: Size) {
                     Point::new(size.width - half, 0.0),
                     Point::new(size.width - half, size.height),
                 ),
-                border_color,
-                right as f64,
+                style.border_right_color,
+                &style.border_style.stroke(right),
             );
         }
         if top > 0.0 {
             let half = top as f64 / 2.0;
             cx.stroke(
                 &Line::new(Point::new(0.0, half), Point::new(size.width, half)),
-                border_color,
-                top as f64,
+                style.border_top_color,
+                &style.border_style.stroke(top),
             );
         }
         if bottom > 0.0 {
@@ -658,9 +788,57 @@ fn paint_border(cx: &mut PaintCx, style: &ComputedStyle, size: Size) {
                     Point::new(0.0, size.height - half),
                     Point::new(size.width, size.height - half),
                 ),
-                border_color,
-                bottom as f64,
+                style.border_bottom_color,
+                &style.border_style.stroke(bottom),
             );
         }
     }
 }
+
+/// The outline of one side of `rect`, counted clockwise from the top, with half
+/// of each corner at its ends, so the sides meet at the middle of the corners
+fn border_side(rect: RoundedRect, side: usize) -> BezPath {
+    let radii = rect.radii();
+    let rect = rect.rect();
+    // the radii of the corners from the top left going clockwise, with their centers
+    let [top_left, top_right, bottom_right, bottom_left] = [
+        radii.top_left,
+        radii.top_right,
+        radii.bottom_right,
+        radii.bottom_left,
+    ];
+    let corners = [
+        (top_left, Point::new(rect.x0 + top_left, rect.y0 + top_left)),
+        (
+            top_right,
+            Point::new(rect.x1 - top_right, rect.y0 + top_right),
+        ),
+        (
+            bottom_right,
+            Point::new(rect.x1 - bottom_right, rect.y1 - bottom_right),
+        ),
+        (
+            bottom_left,
+            Point::new(rect.x0 + bottom_left, rect.y1 - bottom_left),
+        ),
+    ];
+
+    // the middle of the first corner, in the angles of the y-down coordinates
+    let start_angle = -3.0 * FRAC_PI_4 + side as f64 * FRAC_PI_2;
+    let corner_arc = |(radius, center): (f64, Point), start_angle: f64| Arc {
+        center,
+        radii: Vec2::new(radius, radius),
+        start_angle,
+        sweep_angle: FRAC_PI_4,
+        x_rotation: 0.0,
+    };
+    let first = corner_arc(corners[side], start_angle);
+    let second = corner_arc(corners[(side + 1) % 4], start_angle + FRAC_PI_4);
+
+    let mut path = BezPath::new();
+    path.move_to(first.center + Vec2::from_angle(start_angle) * first.radii.x);
+    path.extend(first.append_iter(0.1));
+    path.line_to(second.center + Vec2::from_angle(second.start_angle) * second.radii.x);
+    path.extend(second.append_iter(0.1));
+    path
+}
@@ -11,6 +11,7 @@ pub struct Clip<V: View> {
     child: V,
 }
 
+/// Clips its child to the view, within its rounded corners
 pub fn clip<V: View>(child: impl FnOnce() -> V) -> Clip<V> {
     let cx = AppContext::get_current();
     let id = cx.new_id();
@@ -80,7 +81,13 @@ impl<V: View> View for Clip<V> {
             .get_layout(self.id)
             .map(|layout| Size::new(layout.size.width as f64, layout.size.height as f64))
             .unwrap_or_default();
-        cx.clip(&size.to_rect());
+        let radii = cx.get_computed_style(self.id).border_radii();
+        if radii.as_single_radius() == Some(0.0) {
+            // renderers can clip to a rect much faster than to other shapes
+            cx.clip(&size.to_rect());
+        } else {
+            cx.clip(&size.to_rect().to_rounded_rect(radii));
+        }
         self.child.paint_main(cx);
         cx.restore();
     }
@@ -0,0 +1,251 @@
+use accesskit::{NodeBuilder, Role};
+use floem_renderer::Renderer;
+use glazier::kurbo::{Rect, Size};
+use leptos_reactive::create_effect;
+use sha2::{Digest, Sha256};
+use taffy::{prelude::Node, style::Dimension};
+
+use crate::{
+    app_handle::AppContext,
+    context::AccessibilityCx,
+    id::Id,
+    style::{ComputedStyle, Style},
+    view::{ChangeFlags, View},
+};
+
+/// How an [`Image`] is sized to fit the view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ImageFit {
+    /// Scales the image to fit in the view, keeping its aspect ratio
+    #[default]
+    Contain,
+    /// Scales the image to cover the view, keeping its aspect ratio and cutting off the rest
+    Cover,
+    /// Stretches the image to the size of the view
+    Fill,
+}
+
+/// Decoded pixels in RGBA, row by row, with straight alpha
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct Image {
+    id: Id,
+    img: Option<RgbaImage>,
+    img_hash: Option<Vec<u8>>,
+    img_node: Option<Node>,
+    fit: ImageFit,
+}
+
+/// Shows an image decoded from PNG or JPEG bytes.
+///
+/// Without a size set in its style, the view takes the size of the image.
+/// Bytes that can't be decoded show nothing, and log a warning.
+pub fn image(bytes: impl Fn() -> Vec<u8> + 'static) -> Image {
+    rgba_image(move || {
+        let bytes = bytes();
+        match ::image::load_from_memory(&bytes) {
+            Ok(decoded) => {
+                let decoded = decoded.to_rgba8();
+                RgbaImage {
+                    width: decoded.width(),
+                    height: decoded.height(),
+                    data: decoded.into_raw(),
+                }
+            }
+            Err(err) => {
+                log::warn!("can't decode image: {err}");
+                RgbaImage {
+                    data: Vec::new(),
+                    width: 0,
+                    height: 0,
+                }
+            }
+        }
+    })
+}
+
+/// Shows decoded RGBA pixels
+pub fn rgba_image(img: impl Fn() -> RgbaImage + 'static) -> Image {
+    let cx = AppContext::get_current();
+    let id = cx.new_id();
+    create_effect(cx.scope, move |_| {
+        let new_img = img();
+        id.update_state(new_img, false);
+    });
+    Image {
+        id,
+        img: None,
+        img_hash: None,
+        img_node: None,
+        fit: ImageFit::default(),
+    }
+}
+
+impl Image {
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+}
+
+/// Where an image of `img_size` is drawn in a view of `size`
+fn image_rect(fit: ImageFit, img_size: Size, size: Size) -> Rect {
+    let scale_x = size.width / img_size.width;
+    let scale_y = size.height / img_size.height;
+    let scale = match fit {
+        ImageFit::Fill => return size.to_rect(),
+        ImageFit::Contain => scale_x.min(scale_y),
+        ImageFit::Cover => scale_x.max(scale_y),
+    };
+    Rect::from_center_size(size.to_rect().center(), img_size * scale)
+}
+
+impl View for Image {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn child(&mut self, _id: Id) -> Option<&mut dyn View> {
+        None
+    }
+
+    fn children(&mut self) -> Vec<&mut dyn View> {
+        Vec::new()
+    }
+
+    fn debug_name(&self) -> std::borrow::Cow<'static, str> {
+        "Image".into()
+    }
+
+    fn update(
+        &mut self,
+        cx: &mut crate::context::UpdateCx,
+        state: Box<dyn std::any::Any>,
+    ) -> crate::view::ChangeFlags {
+        if let Ok(img) = state.downcast::<RgbaImage>() {
+            let mut hasher = Sha256::new();
+            hasher.update(img.width.to_le_bytes());
+            hasher.update(img.height.to_le_bytes());
+            hasher.update(&img.data);
+            self.img_hash = Some(hasher.finalize().to_vec());
+            self.img = Some(*img);
+
+            cx.request_layout(self.id());
+            ChangeFlags::LAYOUT
+        } else {
+            ChangeFlags::empty()
+        }
+    }
+
+    fn layout(&mut self, cx: &mut crate::context::LayoutCx) -> taffy::prelude::Node {
+        cx.layout_node(self.id, true, |cx| {
+            if self.img_node.is_none() {
+                self.img_node = Some(
+                    cx.app_state
+                        .taffy
+                        .new_leaf(taffy::style::Style::DEFAULT)
+                        .unwrap(),
+                );
+            }
+            let img_node = self.img_node.unwrap();
+
+            let (width, height) = self
+                .img
+                .as_ref()
+                .map(|img| (img.width as f32, img.height as f32))
+                .unwrap_or((0.0, 0.0));
+            let style = Style::BASE
+                .width(Dimension::Points(width))
+                .height(Dimension::Points(height))
+                .compute(&ComputedStyle::default())
+                .to_taffy_style();
+            let _ = cx.app_state.taffy.set_style(img_node, style);
+
+            vec![img_node]
+        })
+    }
+
+    fn event(
+        &mut self,
+        _cx: &mut crate::context::EventCx,
+        _id_path: Option<&[Id]>,
+        _event: crate::event::Event,
+    ) -> bool {
+        false
+    }
+
+    fn paint(&mut self, cx: &mut crate::context::PaintCx) {
+        let img = match self.img.as_ref() {
+            Some(img) if img.width > 0 && img.height > 0 => img,
+            _ => return,
+        };
+        let layout = cx.get_layout(self.id).unwrap();
+        let size = Size::new(layout.size.width as f64, layout.size.height as f64);
+        let img_size = Size::new(img.width as f64, img.height as f64);
+        let rect = image_rect(self.fit, img_size, size);
+        cx.save();
+        if self.fit == ImageFit::Cover {
+            cx.clip(&size.to_rect());
+        }
+
+        let hash = self.img_hash.as_ref().unwrap();
+        cx.draw_img(
+            floem_renderer::Img {
+                data: &img.data,
+                width: img.width,
+                height: img.height,
+                hash,
+            },
+            rect,
+        );
+        cx.restore();
+    }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        Some(NodeBuilder::new(Role::Image))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glazier::kurbo::{Rect, Size};
+
+    use crate::{test_harness::TestHarness, views::stack};
+
+    use super::{image_rect, rgba_image, ImageFit, RgbaImage};
+
+    fn red_image() -> RgbaImage {
+        RgbaImage {
+            data: [255, 0, 0, 255].repeat(4 * 2),
+            width: 4,
+            height: 2,
+        }
+    }
+
+    #[test]
+    fn fit_modes() {
+        let img_size = Size::new(4.0, 2.0);
+        let size = Size::new(100.0, 100.0);
+        assert_eq!(
+            image_rect(ImageFit::Contain, img_size, size),
+            Rect::new(0.0, 25.0, 100.0, 75.0)
+        );
+        assert_eq!(
+            image_rect(ImageFit::Cover, img_size, size),
+            Rect::new(-50.0, 0.0, 150.0, 100.0)
+        );
+        assert_eq!(image_rect(ImageFit::Fill, img_size, size), size.to_rect());
+    }
+
+    #[test]
+    fn takes_the_size_of_the_image() {
+        let harness = TestHarness::new(|| stack(|| (rgba_image(red_image),)), (100.0, 100.0));
+        let image_id = harness.root_id().first_child().unwrap();
+        assert_eq!(harness.size_of(image_id), Some(Size::new(4.0, 2.0)));
+    }
+}
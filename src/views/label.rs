@@ -1,5 +1,7 @@
 use std::any::Any;
 
+use accesskit::{NodeBuilder, Role};
+
 use crate::{
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     style::{ComputedStyle, TextOverflow},
@@ -15,7 +17,7 @@ use vello::peniko::Color;
 
 use crate::{
     app_handle::AppContext,
-    context::{EventCx, UpdateCx},
+    context::{AccessibilityCx, EventCx, UpdateCx},
     event::Event,
     id::Id,
     style::Style,
@@ -325,4 +327,10 @@ impl View for Label {
             cx.draw_text(self.text_layout.as_ref().unwrap(), point);
         }
     }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        let mut node = NodeBuilder::new(Role::StaticText);
+        node.set_name(self.label.as_str());
+        Some(node)
+    }
 }
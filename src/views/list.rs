@@ -3,14 +3,21 @@ use std::{
     marker::PhantomData,
 };
 
+use accesskit::{NodeBuilder, Role};
 use glazier::kurbo::Rect;
 use leptos_reactive::{create_effect, ScopeDisposer};
 use rustc_hash::FxHasher;
 use smallvec::SmallVec;
 
+use taffy::{
+    prelude::Node,
+    style::{Dimension, LengthPercentageAuto, Position},
+};
+
 use crate::{
+    animate::{AnimId, Transition},
     app_handle::AppContext,
-    context::{AppState, EventCx, UpdateCx},
+    context::{AccessibilityCx, AppState, EventCx, LayoutCx, PaintCx, UpdateCx},
     id::Id,
     view::{ChangeFlags, View},
 };
@@ -29,6 +36,7 @@ where
 {
     id: Id,
     children: Vec<Option<(V, ScopeDisposer)>>,
+    transitions: ChildTransitions<V>,
     view_fn: VF,
     phatom: PhantomData<T>,
     cx: AppContext,
@@ -79,12 +87,33 @@ where
     List {
         id,
         children: Vec::new(),
+        transitions: ChildTransitions::new(id),
         view_fn,
         phatom: PhantomData::default(),
         cx: child_cx,
     }
 }
 
+impl<V: View, VF, T> List<V, VF, T>
+where
+    VF: Fn(T) -> V + 'static,
+{
+    /// Runs `transition` on the views of the items that are added after the
+    /// first build
+    pub fn enter(mut self, transition: Transition) -> Self {
+        self.transitions.enter = Some(transition);
+        self
+    }
+
+    /// Runs `transition` on the views of the items that are removed, which are
+    /// only taken out of the list once it completes. Meanwhile they keep their
+    /// place and size, without taking up room from the other views.
+    pub fn exit(mut self, transition: Transition) -> Self {
+        self.transitions.exit = Some(transition);
+        self
+    }
+}
+
 impl<V: View + 'static, VF, T> View for List<V, VF, T>
 where
     VF: Fn(T) -> V + 'static,
@@ -101,7 +130,7 @@ where
         if let Some(child) = child {
             child.as_mut().map(|(view, _)| view as &mut dyn View)
         } else {
-            None
+            self.transitions.child(id)
         }
     }
 
@@ -122,10 +151,24 @@ where
         cx: &mut UpdateCx,
         state: Box<dyn std::any::Any>,
     ) -> crate::view::ChangeFlags {
+        let state = match state.downcast::<TransitionEnd>() {
+            Ok(end) => {
+                self.transitions.end(cx.app_state, *end);
+                cx.request_layout(self.id());
+                return ChangeFlags::LAYOUT;
+            }
+            Err(state) => state,
+        };
         if let Ok(diff) = state.downcast() {
             AppContext::save();
             AppContext::set_current(self.cx);
-            apply_diff(cx.app_state, *diff, &mut self.children, &self.view_fn);
+            apply_diff(
+                cx.app_state,
+                *diff,
+                &mut self.children,
+                &self.view_fn,
+                Some(&mut self.transitions),
+            );
             AppContext::restore();
             cx.request_layout(self.id());
             ChangeFlags::LAYOUT
@@ -136,11 +179,12 @@ where
 
     fn layout(&mut self, cx: &mut crate::context::LayoutCx) -> taffy::prelude::Node {
         cx.layout_node(self.id, true, |cx| {
-            let nodes = self
+            let mut nodes = self
                 .children
                 .iter_mut()
                 .filter_map(|child| Some(child.as_mut()?.0.layout_main(cx)))
                 .collect::<Vec<_>>();
+            nodes.extend(self.transitions.layout(cx));
             nodes
         })
     }
@@ -152,7 +196,7 @@ where
                 layout_rect = layout_rect.union(child.compute_layout_main(cx));
             }
         }
-        Some(layout_rect)
+        Some(layout_rect.union(self.transitions.compute_layout(cx)))
     }
 
     fn event(
@@ -178,6 +222,11 @@ where
                 child.paint_main(cx);
             }
         }
+        self.transitions.paint(cx);
+    }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        Some(NodeBuilder::new(Role::List))
     }
 }
 
@@ -306,42 +355,180 @@ pub(crate) fn diff<K: Eq + Hash, V>(from: &FxIndexSet<K>, to: &FxIndexSet<K>) ->
     diffs
 }
 
-fn remove_index<V: View>(
-    app_state: &mut AppState,
-    children: &mut [Option<(V, ScopeDisposer)>],
-    index: usize,
-) -> Option<()> {
-    let (view, disposer) = std::mem::take(&mut children[index])?;
-    let id = view.id();
-    if let Some(view_state) = app_state.view_states.remove(&id) {
-        let node = view_state.node;
-        let mut nodes = Vec::new();
-        let mut parents = Vec::new();
-        parents.push(node);
-        nodes.push(node);
-        while !parents.is_empty() {
-            let parent = parents.pop().unwrap();
-            if let Ok(children) = app_state.taffy.children(parent) {
-                for child in children {
-                    nodes.push(child);
-                    parents.push(child);
+/// The enter and exit transitions of the children of a list or tab, with the
+/// removed children that are still running their exit transitions
+pub(crate) struct ChildTransitions<V> {
+    parent: Id,
+    pub(crate) enter: Option<Transition>,
+    pub(crate) exit: Option<Transition>,
+    /// Whether the first diff was applied. The children it adds are there from
+    /// the start, so they don't run the enter transition.
+    built: bool,
+    leaving: Vec<LeavingChild<V>>,
+}
+
+struct LeavingChild<V> {
+    view: V,
+    disposer: ScopeDisposer,
+    /// Where the child was in its parent when it was removed
+    rect: Rect,
+}
+
+/// Sent to the parent when the transition of one of its children ends
+pub(crate) enum TransitionEnd {
+    Entered { child: Id, animation: AnimId },
+    Exited(Id),
+}
+
+impl<V: View> ChildTransitions<V> {
+    pub(crate) fn new(parent: Id) -> Self {
+        Self {
+            parent,
+            enter: None,
+            exit: None,
+            built: false,
+            leaving: Vec::new(),
+        }
+    }
+
+    /// Starts the enter transition of a child that was just added
+    fn enter(&self, app_state: &mut AppState, child: Id) {
+        if let Some(enter) = self.enter.as_ref() {
+            let parent = self.parent;
+            let animation = enter.enter_animation();
+            let animation_id = animation.id();
+            let animation = animation.on_complete(move || {
+                parent.update_state(
+                    TransitionEnd::Entered {
+                        child,
+                        animation: animation_id,
+                    },
+                    false,
+                );
+            });
+            app_state.animated.insert(child);
+            app_state.view_state(child).transition = Some(animation);
+            app_state.request_layout(child);
+        }
+    }
+
+    /// Starts the exit transition of a child that was just removed, or removes
+    /// it right away without one
+    fn leave(&mut self, app_state: &mut AppState, view: V, disposer: ScopeDisposer) {
+        let exit = match self.exit.as_ref() {
+            Some(exit) => exit,
+            None => {
+                remove_view(app_state, view, disposer);
+                return;
+            }
+        };
+        let parent = self.parent;
+        let child = view.id();
+        let rect = app_state
+            .get_layout(child)
+            .map(|layout| {
+                Rect::new(
+                    layout.location.x as f64,
+                    layout.location.y as f64,
+                    (layout.location.x + layout.size.width) as f64,
+                    (layout.location.y + layout.size.height) as f64,
+                )
+            })
+            .unwrap_or_default();
+        let animation = exit.exit_animation().on_complete(move || {
+            parent.update_state(TransitionEnd::Exited(child), false);
+        });
+        app_state.animated.insert(child);
+        app_state.view_state(child).transition = Some(animation);
+        app_state.request_layout(child);
+        self.leaving.push(LeavingChild {
+            view,
+            disposer,
+            rect,
+        });
+    }
+
+    pub(crate) fn end(&mut self, app_state: &mut AppState, end: TransitionEnd) {
+        match end {
+            TransitionEnd::Entered { child, animation } => {
+                // the child might have started leaving since
+                if let Some(view_state) = app_state.view_states.get_mut(&child) {
+                    if view_state.transition.as_ref().map(|a| a.id()) == Some(animation) {
+                        view_state.transition = None;
+                        if view_state.animation.is_none() {
+                            app_state.animated.remove(&child);
+                        }
+                        app_state.request_layout(child);
+                    }
+                }
+            }
+            TransitionEnd::Exited(child) => {
+                if let Some(index) = self.leaving.iter().position(|c| c.view.id() == child) {
+                    let LeavingChild { view, disposer, .. } = self.leaving.remove(index);
+                    remove_view(app_state, view, disposer);
                 }
             }
-        }
-        for node in nodes {
-            let _ = app_state.taffy.remove(node);
         }
     }
 
-    let mut all_ids = id.all_chilren();
-    all_ids.push(id);
-    for id in all_ids {
-        id.remove_idpath();
-        app_state.view_states.remove(&id);
+    pub(crate) fn child(&mut self, id: Id) -> Option<&mut dyn View> {
+        self.leaving
+            .iter_mut()
+            .find(|c| c.view.id() == id)
+            .map(|c| &mut c.view as &mut dyn View)
     }
 
-    disposer.dispose();
-    Some(())
+    /// Lays out the leaving children where they were, out of the flow of the
+    /// other children. The placement is set on their nodes after their own
+    /// style, so restyling them while they leave doesn't move them.
+    pub(crate) fn layout(&mut self, cx: &mut LayoutCx) -> Vec<Node> {
+        self.leaving
+            .iter_mut()
+            .map(|child| {
+                let node = child.view.layout_main(cx);
+                let rect = child.rect;
+                let taffy = &mut cx.app_state.taffy;
+                if let Ok(style) = taffy.style(node) {
+                    let style = taffy::style::Style {
+                        position: Position::Absolute,
+                        inset: taffy::geometry::Rect {
+                            left: LengthPercentageAuto::Points(rect.x0 as f32),
+                            top: LengthPercentageAuto::Points(rect.y0 as f32),
+                            right: LengthPercentageAuto::Auto,
+                            bottom: LengthPercentageAuto::Auto,
+                        },
+                        size: taffy::geometry::Size {
+                            width: Dimension::Points(rect.width() as f32),
+                            height: Dimension::Points(rect.height() as f32),
+                        },
+                        // the location already accounts for the margins
+                        margin: taffy::geometry::Rect {
+                            left: LengthPercentageAuto::Points(0.0),
+                            top: LengthPercentageAuto::Points(0.0),
+                            ..style.margin
+                        },
+                        ..style.clone()
+                    };
+                    let _ = taffy.set_style(node, style);
+                }
+                node
+            })
+            .collect()
+    }
+
+    pub(crate) fn compute_layout(&mut self, cx: &mut LayoutCx) -> Rect {
+        let mut layout_rect = Rect::ZERO;
+        for child in &mut self.leaving {
+            layout_rect = layout_rect.union(child.view.compute_layout_main(cx));
+        }
+        layout_rect
+    }
+
+    pub(crate) fn paint(&mut self, cx: &mut PaintCx) {
+        for child in &mut self.leaving {
+            child.view.paint_main(cx);
+        }
+    }
 }
 
 pub(super) fn apply_diff<T, V, VF>(
@@ -349,6 +536,7 @@ pub(super) fn apply_diff<T, V, VF>(
     mut diff: Diff<T>,
     children: &mut Vec<Option<(V, ScopeDisposer)>>,
     view_fn: &VF,
+    mut transitions: Option<&mut ChildTransitions<V>>,
 ) where
     V: View,
     VF: Fn(T) -> V + 'static,
@@ -373,13 +561,13 @@ pub(super) fn apply_diff<T, V, VF>(
     // 4. Add
     if diff.clear {
         for i in 0..children.len() {
-            remove_index(app_state, children, i);
+            remove_index(app_state, children, i, transitions.as_deref_mut());
         }
         diff.removed.clear();
     }
 
     for DiffOpRemove { at } in diff.removed {
-        remove_index(app_state, children, at);
+        remove_index(app_state, children, at, transitions.as_deref_mut());
     }
 
     for DiffOpMove { from, to } in diff.moved {
@@ -387,6 +575,9 @@ pub(super) fn apply_diff<T, V, VF>(
         items_to_move.push((to, item));
     }
 
+    let entering = transitions.as_deref_mut().and_then(|transitions| {
+        std::mem::replace(&mut transitions.built, true).then_some(&*transitions)
+    });
     for DiffOpAdd { at, view } in diff.added {
         children[at] = view.map(|value| {
             let cx = AppContext::get_current();
@@ -400,6 +591,9 @@ pub(super) fn apply_diff<T, V, VF>(
                 view
             })
         });
+        if let (Some(transitions), Some((view, _))) = (entering, &children[at]) {
+            transitions.enter(app_state, view.id());
+        }
     }
 
     for (to, each_item) in items_to_move {
@@ -410,3 +604,143 @@ pub(super) fn apply_diff<T, V, VF>(
     // items
     children.retain(|c| c.is_some());
 }
+
+fn remove_index<V: View>(
+    app_state: &mut AppState,
+    children: &mut [Option<(V, ScopeDisposer)>],
+    index: usize,
+    transitions: Option<&mut ChildTransitions<V>>,
+) -> Option<()> {
+    let (view, disposer) = std::mem::take(&mut children[index])?;
+    match transitions {
+        Some(transitions) => transitions.leave(app_state, view, disposer),
+        None => remove_view(app_state, view, disposer),
+    }
+    Some(())
+}
+
+fn remove_view<V: View>(app_state: &mut AppState, view: V, disposer: ScopeDisposer) {
+    let id = view.id();
+    if let Some(view_state) = app_state.view_states.remove(&id) {
+        let node = view_state.node;
+        let mut nodes = Vec::new();
+        let mut parents = Vec::new();
+        parents.push(node);
+        nodes.push(node);
+        while !parents.is_empty() {
+            let parent = parents.pop().unwrap();
+            if let Ok(children) = app_state.taffy.children(parent) {
+                for child in children {
+                    nodes.push(child);
+                    parents.push(child);
+                }
+            }
+        }
+        for node in nodes {
+            let _ = app_state.taffy.remove(node);
+        }
+    }
+
+    let mut all_ids = id.all_chilren();
+    all_ids.push(id);
+    for id in all_ids {
+        id.remove_idpath();
+        app_state.view_states.remove(&id);
+        app_state.animated.remove(&id);
+        app_state.remove_text_fields_of(id);
+    }
+
+    disposer.dispose();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use leptos_reactive::{create_rw_signal, SignalGet, SignalSet};
+
+    use crate::{
+        animate::Transition,
+        style::Style,
+        test_harness::{TestHarness, ViewIds},
+        views::{empty, list, Decorators},
+        AppContext,
+    };
+
+    #[test]
+    fn list_keeps_removed_views_until_they_exit() {
+        let items = Rc::new(Cell::new(None));
+        let list_items = items.clone();
+        let views = ViewIds::default();
+        let list_views = views.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let items = create_rw_signal(cx.scope, vec![1, 2]);
+                list_items.set(Some(items));
+                list(
+                    move || items.get(),
+                    |item| *item,
+                    move |_| list_views.record(empty().style(|| Style::BASE.size_px(50.0, 50.0))),
+                )
+                .exit(Transition::fade().duration(Duration::from_millis(100)))
+            },
+            (200.0, 200.0),
+        );
+        let items = items.get().unwrap();
+        let (first, second) = (views.get(0), views.get(1));
+        assert_eq!(harness.layout_rect(second).unwrap().x0, 50.0);
+
+        items.set(vec![2]);
+        harness.update();
+        // the other view takes the room right away, while the removed one fades out
+        assert!(harness.layout_rect(first).is_some());
+        assert_eq!(harness.layout_rect(second).unwrap().x0, 0.0);
+
+        harness.advance_time(Duration::from_millis(50));
+        let opacity = harness.app().app_state().view_states[&first]
+            .computed_style
+            .opacity;
+        assert!(opacity > 0.0 && opacity < 1.0, "{opacity}");
+
+        harness.advance_time(Duration::from_millis(60));
+        assert!(harness.layout_rect(first).is_none());
+        assert_eq!(harness.layout_rect(second).unwrap().x0, 0.0);
+    }
+
+    #[test]
+    fn list_enters_only_views_added_after_it_is_built() {
+        let items = Rc::new(Cell::new(None));
+        let list_items = items.clone();
+        let views = ViewIds::default();
+        let list_views = views.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let items = create_rw_signal(cx.scope, vec![1]);
+                list_items.set(Some(items));
+                list(
+                    move || items.get(),
+                    |item| *item,
+                    move |_| list_views.record(empty().style(|| Style::BASE.size_px(50.0, 50.0))),
+                )
+                .enter(Transition::fade().duration(Duration::from_millis(100)))
+            },
+            (200.0, 200.0),
+        );
+        let opacity = |harness: &TestHarness<_>, id| {
+            harness.app().app_state().view_states[&id]
+                .computed_style
+                .opacity
+        };
+        let items = items.get().unwrap();
+        assert_eq!(opacity(&harness, views.get(0)), 1.0);
+
+        items.set(vec![1, 2]);
+        harness.update();
+        harness.advance_time(Duration::from_millis(50));
+        assert_eq!(opacity(&harness, views.get(0)), 1.0);
+        let entering = opacity(&harness, views.get(1));
+        assert!(entering > 0.0 && entering < 1.0, "{entering}");
+    }
+}
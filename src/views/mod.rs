@@ -10,6 +10,9 @@ pub use list::*;
 mod svg;
 pub use svg::*;
 
+mod image;
+pub use image::*;
+
 mod clip;
 pub use clip::*;
 
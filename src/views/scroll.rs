@@ -1,3 +1,4 @@
+use accesskit::{NodeBuilder, Role};
 use floem_renderer::Renderer;
 use glazier::{
     kurbo::{Point, Rect, Size, Vec2},
@@ -8,11 +9,11 @@ use taffy::{
     prelude::Node,
     style::{Dimension, Position},
 };
-use vello::peniko::Color;
+use vello::peniko::{Color, Stroke};
 
 use crate::{
     app_handle::AppContext,
-    context::{AppState, LayoutCx, PaintCx},
+    context::{AccessibilityCx, AppState, LayoutCx, PaintCx},
     event::Event,
     id::Id,
     style::{ComputedStyle, Style, StyleValue},
@@ -21,6 +22,8 @@ use crate::{
 
 enum ScrollState {
     EnsureVisble(Rect),
+    /// A rect in the coordinates of a view inside the scroll
+    EnsureDescendantVisible(Id, Rect),
     ScrollDelta(Vec2),
     ScrollTo(Point),
     ScrollBarColor(Color),
@@ -87,6 +90,16 @@ pub fn scroll<V: View>(child: impl FnOnce() -> V) -> Scroll<V> {
     }
 }
 
+/// Pans every scroll view around the view `id`, so that `rect`, given in the
+/// coordinates of that view, becomes visible
+pub(crate) fn scroll_to_visible(id: Id, rect: Rect) {
+    let mut ancestor = id.parent();
+    while let Some(scroll_id) = ancestor {
+        scroll_id.update_state(ScrollState::EnsureDescendantVisible(id, rect), true);
+        ancestor = scroll_id.parent();
+    }
+}
+
 impl<V: View> Scroll<V> {
     pub fn scroll_bar_color(self, color: impl Fn() -> Color + 'static) -> Self {
         let cx = AppContext::get_current();
@@ -213,6 +226,19 @@ impl<V: View> Scroll<V> {
         self.clamp_child_viewport(app_state, self.child_viewport.with_origin(new_origin));
     }
 
+    /// Translates a rect of a view inside the scroll to the coordinates of the scrolled child
+    fn descendant_rect(&self, app_state: &AppState, id: Id, rect: Rect) -> Option<Rect> {
+        let child_id = self.child.id();
+        let mut rect = rect;
+        let mut current = id;
+        while current != child_id {
+            let layout = app_state.get_layout(current)?;
+            rect = rect + Vec2::new(layout.location.x as f64, layout.location.y as f64);
+            current = current.parent()?;
+        }
+        Some(rect)
+    }
+
     fn update_size(&mut self, app_state: &mut AppState) {
         let child_size = self.child_size;
         let new_child_size = self.child_size(app_state).unwrap_or_default();
@@ -309,7 +335,7 @@ impl<V: View> Scroll<V> {
             let rect = (bounds - scroll_offset).inset(-edge_width / 2.0);
             cx.fill(&rect, color);
             if edge_width > 0.0 {
-                cx.stroke(&rect, color, edge_width);
+                cx.stroke(&rect, color, &Stroke::new(edge_width as f32));
             }
         }
 
@@ -318,7 +344,7 @@ impl<V: View> Scroll<V> {
             let rect = (bounds - scroll_offset).inset(-edge_width / 2.0);
             cx.fill(&rect, color);
             if edge_width > 0.0 {
-                cx.stroke(&rect, color, edge_width);
+                cx.stroke(&rect, color, &Stroke::new(edge_width as f32));
             }
         }
     }
@@ -457,6 +483,13 @@ impl<V: View> View for Scroll<V> {
                 ScrollState::EnsureVisble(rect) => {
                     self.pan_to_visible(cx.app_state, rect);
                 }
+                ScrollState::EnsureDescendantVisible(id, rect) => {
+                    match self.descendant_rect(cx.app_state, id, rect) {
+                        Some(rect) => self.pan_to_visible(cx.app_state, rect),
+                        // the view isn't inside this scroll
+                        None => return ChangeFlags::empty(),
+                    }
+                }
                 ScrollState::ScrollDelta(delta) => {
                     self.scroll_delta(cx.app_state, delta);
                 }
@@ -620,4 +653,13 @@ impl<V: View> View for Scroll<V> {
             self.draw_bars(cx);
         }
     }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        let mut node = NodeBuilder::new(Role::ScrollView);
+        node.set_scroll_x(self.child_viewport.x0);
+        node.set_scroll_y(self.child_viewport.y0);
+        node.set_scroll_x_max((self.child_size.width - self.child_viewport.width()).max(0.0));
+        node.set_scroll_y_max((self.child_size.height - self.child_viewport.height()).max(0.0));
+        Some(node)
+    }
 }
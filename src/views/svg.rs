@@ -1,15 +1,17 @@
+use accesskit::{CheckedState, NodeBuilder, Role};
 use floem_renderer::{
     usvg::Tree,
     usvg::{self, TreeParsing},
     Renderer,
 };
 use glazier::kurbo::Size;
-use leptos_reactive::{create_effect, SignalGet};
+use leptos_reactive::{create_effect, ReadSignal, SignalGet, SignalGetUntracked};
 use sha2::{Digest, Sha256};
 use vello::peniko::Color;
 
 use crate::{
     app_handle::AppContext,
+    context::AccessibilityCx,
     id::Id,
     style::Style,
     view::{ChangeFlags, View},
@@ -20,6 +22,8 @@ pub struct Svg {
     id: Id,
     svg_tree: Option<Tree>,
     svg_hash: Option<Vec<u8>>,
+    // set when the svg is drawn as a checkbox, so it can be reported as one
+    checked: Option<ReadSignal<bool>>,
 }
 
 pub fn svg(svg_str: impl Fn() -> String + 'static) -> Svg {
@@ -33,26 +37,28 @@ pub fn svg(svg_str: impl Fn() -> String + 'static) -> Svg {
         id,
         svg_tree: None,
         svg_hash: None,
+        checked: None,
     }
 }
 
 /// Renders a checkbox using an svg and the provided checked signal.
 /// Can be combined with a label and a stack with a click event (as in `examples/widget-gallery`).
-pub fn checkbox(checked: leptos_reactive::ReadSignal<bool>) -> Svg {
+pub fn checkbox(checked: ReadSignal<bool>) -> Svg {
     const CHECKBOX_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-2 -2 16 16"><polygon points="5.19,11.83 0.18,7.44 1.82,5.56 4.81,8.17 10,1.25 12,2.75" /></svg>"#;
     let svg_str = move || if checked.get() { CHECKBOX_SVG } else { "" }.to_string();
 
-    svg(svg_str)
-        .style(|| {
-            Style::BASE
-                .width_px(20.)
-                .height_px(20.)
-                .border_color(Color::BLACK)
-                .border(1.)
-                .border_radius(5.)
-                .margin_right_px(5.)
-        })
-        .keyboard_navigatable()
+    let mut svg = svg(svg_str);
+    svg.checked = Some(checked);
+    svg.style(|| {
+        Style::BASE
+            .width_px(20.)
+            .height_px(20.)
+            .border_color(Color::BLACK)
+            .border(1.)
+            .border_radius(5.)
+            .margin_right_px(5.)
+    })
+    .keyboard_navigatable()
 }
 
 impl View for Svg {
@@ -115,4 +121,15 @@ impl View for Svg {
             cx.draw_svg(floem_renderer::Svg { tree, hash }, rect, color);
         }
     }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        let checked = self.checked?;
+        let mut node = NodeBuilder::new(Role::CheckBox);
+        node.set_checked_state(if checked.get_untracked() {
+            CheckedState::True
+        } else {
+            CheckedState::False
+        });
+        Some(node)
+    }
 }
@@ -1,18 +1,22 @@
 use std::{hash::Hash, marker::PhantomData};
 
+use accesskit::{NodeBuilder, Role};
 use glazier::kurbo::Rect;
 use leptos_reactive::{create_effect, ScopeDisposer};
 use smallvec::SmallVec;
 use taffy::style::Display;
 
 use crate::{
+    animate::Transition,
     app_handle::AppContext,
-    context::{EventCx, UpdateCx},
+    context::{AccessibilityCx, EventCx, UpdateCx},
     id::Id,
     view::{ChangeFlags, View},
 };
 
-use super::{apply_diff, diff, Diff, DiffOpAdd, FxIndexSet, HashRun};
+use super::{
+    apply_diff, diff, ChildTransitions, Diff, DiffOpAdd, FxIndexSet, HashRun, TransitionEnd,
+};
 
 enum TabState<V> {
     Diff(Box<Diff<V>>),
@@ -28,6 +32,7 @@ where
     id: Id,
     active: usize,
     children: Vec<Option<(V, ScopeDisposer)>>,
+    transitions: ChildTransitions<V>,
     view_fn: VF,
     phatom: PhantomData<T>,
     cx: AppContext,
@@ -91,12 +96,32 @@ where
         id,
         active: 0,
         children: Vec::new(),
+        transitions: ChildTransitions::new(id),
         view_fn,
         phatom: PhantomData::default(),
         cx: child_cx,
     }
 }
 
+impl<V: View, VF, T> Tab<V, VF, T>
+where
+    VF: Fn(T) -> V + 'static,
+{
+    /// Runs `transition` on the views of the items that are added after the
+    /// first build
+    pub fn enter(mut self, transition: Transition) -> Self {
+        self.transitions.enter = Some(transition);
+        self
+    }
+
+    /// Runs `transition` on the views of the items that are removed, which are
+    /// only taken out of the tab once it completes
+    pub fn exit(mut self, transition: Transition) -> Self {
+        self.transitions.exit = Some(transition);
+        self
+    }
+}
+
 impl<V: View + 'static, VF, T> View for Tab<V, VF, T>
 where
     VF: Fn(T) -> V + 'static,
@@ -113,7 +138,7 @@ where
         if let Some(child) = child {
             child.as_mut().map(|(view, _)| view as &mut dyn View)
         } else {
-            None
+            self.transitions.child(id)
         }
     }
 
@@ -134,12 +159,26 @@ where
         cx: &mut UpdateCx,
         state: Box<dyn std::any::Any>,
     ) -> crate::view::ChangeFlags {
+        let state = match state.downcast::<TransitionEnd>() {
+            Ok(end) => {
+                self.transitions.end(cx.app_state, *end);
+                cx.request_layout(self.id());
+                return ChangeFlags::LAYOUT;
+            }
+            Err(state) => state,
+        };
         if let Ok(state) = state.downcast::<TabState<T>>() {
             match *state {
                 TabState::Diff(diff) => {
                     AppContext::save();
                     AppContext::set_current(self.cx);
-                    apply_diff(cx.app_state, *diff, &mut self.children, &self.view_fn);
+                    apply_diff(
+                        cx.app_state,
+                        *diff,
+                        &mut self.children,
+                        &self.view_fn,
+                        Some(&mut self.transitions),
+                    );
                     AppContext::restore();
                 }
                 TabState::Active(active) => {
@@ -158,7 +197,7 @@ where
 
     fn layout(&mut self, cx: &mut crate::context::LayoutCx) -> taffy::prelude::Node {
         cx.layout_node(self.id, true, |cx| {
-            let nodes = self
+            let mut nodes = self
                 .children
                 .iter_mut()
                 .enumerate()
@@ -175,6 +214,7 @@ where
                     Some(node)
                 })
                 .collect::<Vec<_>>();
+            nodes.extend(self.transitions.layout(cx));
             nodes
         })
     }
@@ -186,7 +226,7 @@ where
                 layout_rect = layout_rect.union(child.compute_layout_main(cx));
             }
         }
-        Some(layout_rect)
+        Some(layout_rect.union(self.transitions.compute_layout(cx)))
     }
 
     fn event(
@@ -210,5 +250,13 @@ where
         if let Some(Some((child, _))) = self.children.get_mut(self.active) {
             child.paint_main(cx);
         }
+        // the removed children keep the display they had, so only the one that
+        // was active is seen leaving
+        self.transitions.paint(cx);
+    }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        // only the active child is visible, the others are left out as hidden views
+        Some(NodeBuilder::new(Role::TabPanel))
     }
 }
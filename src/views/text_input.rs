@@ -1,6 +1,10 @@
-use crate::{context::LayoutCx, style::CursorStyle};
+use crate::{
+    context::{AppState, LayoutCx},
+    style::CursorStyle,
+};
+use accesskit::{NodeBuilder, Role};
 use leptos_reactive::{
-    create_effect, RwSignal, SignalGet, SignalGetUntracked, SignalUpdate, SignalWith,
+    create_effect, RwSignal, SignalGet, SignalGetUntracked, SignalSet, SignalWith,
 };
 use taffy::{
     prelude::{Layout, Node},
@@ -17,7 +21,10 @@ use crate::{peniko::Color, style::Style, view::View, AppContext};
 
 use std::{
     any::Any,
+    borrow::Cow,
+    cell::RefCell,
     ops::Range,
+    rc::Rc,
     time::{Duration, Instant},
 };
 
@@ -27,26 +34,86 @@ use crate::{
 };
 use glazier::{
     keyboard_types::Key,
-    kurbo::{Point, Rect},
-    Modifiers,
+    kurbo::{Point, Rect, Vec2},
+    text::{Action as ImeAction, Affinity, HitTestPoint, InputHandler, Selection},
+    Modifiers, TextFieldToken,
 };
 
 use crate::{
-    context::{EventCx, UpdateCx},
+    context::{AccessibilityCx, EventCx, UpdateCx},
+    edit_history::{EditHistory, EditKind},
     event::Event,
     id::Id,
     view::ChangeFlags,
 };
 
-use super::Decorators;
+use super::{scroll_to_visible, Decorators};
+
+/// What an undo restores
+struct TextInputState {
+    text: String,
+    cursor: usize,
+    selection: Range<usize>,
+}
+
+/// What the window's input method sees of a [`TextInput`], shared with the
+/// input handler it locks while composing text
+#[derive(Default)]
+struct ImeState {
+    anchor: usize,
+    active: usize,
+    /// The text being composed, which is part of the buffer until it's committed
+    composition: Option<Range<usize>>,
+    text_layout: Option<TextLayout>,
+    /// Where the text is drawn, in the coordinates of the window before scaling
+    text_origin: Point,
+    scale: f64,
+    /// Set by the input handler, so the input picks up the changes it made
+    changed: bool,
+    /// The states from before the edits the input method made, which the input
+    /// records in its history when it picks up the changes
+    edits: Vec<TextInputState>,
+}
+
+/// The edits a [`TextInput`] accepts, shared with its input handler
+#[derive(Default)]
+struct EditRules {
+    max_length: Option<usize>,
+    filter: Option<Box<dyn Fn(char) -> bool>>,
+    validator: Option<Box<dyn Fn(&str) -> bool>>,
+}
+
+impl EditRules {
+    /// The part of `text` that can replace `replaced` in `buff`, without the characters
+    /// the filter rejects or the ones over the max length
+    fn accepted_text(&self, buff: &str, replaced: Range<usize>, text: &str) -> String {
+        let chars = text.chars().filter(|ch| {
+            self.filter
+                .as_ref()
+                .map(|filter| filter(*ch))
+                .unwrap_or(true)
+        });
+        match self.max_length {
+            Some(max_length) => {
+                let kept = buff.chars().count() - buff[replaced].chars().count();
+                chars.take(max_length.saturating_sub(kept)).collect()
+            }
+            None => chars.collect(),
+        }
+    }
+
+    fn is_valid(&self, text: &str) -> bool {
+        self.validator
+            .as_ref()
+            .map(|validator| validator(text))
+            .unwrap_or(true)
+    }
+}
 
 enum InputKind {
     SingleLine,
-    #[allow(unused)]
-    MultiLine {
-        //TODO:
-        line_index: usize,
-    },
+    /// Wraps the text at the width of the input, and grows vertically with it
+    MultiLine,
 }
 
 pub struct TextInput {
@@ -78,6 +145,25 @@ pub struct TextInput {
     font_weight: Option<Weight>,
     font_style: Option<FontStyle>,
     input_kind: InputKind,
+    // Shows bullets instead of the text
+    is_password: bool,
+    rules: Rc<EditRules>,
+    placeholder: Option<String>,
+    placeholder_buf: Option<TextLayout>,
+    // The width the text wraps at for multi line input
+    wrap_width: Option<f32>,
+    // The x the cursor tries to stay at when moving between lines
+    preferred_cursor_x: Option<f64>,
+    // Set when the cursor moved, so enclosing scroll views can follow it after layout
+    ensure_cursor_visible: bool,
+    history: EditHistory<TextInputState>,
+    // Registered with the window while the input is focused
+    text_field: Option<TextFieldToken>,
+    ime: Rc<RefCell<ImeState>>,
+    // The preedit text of the input method
+    composition: Option<Range<usize>>,
+    // Set when the input changed its text or selection, which the input method has to know
+    ime_reset: bool,
     cursor_width: f64, // TODO: make this configurable
     is_focused: bool,
     last_cursor_action_on: Instant,
@@ -94,6 +180,10 @@ pub enum Movement {
 pub enum Direction {
     Left,
     Right,
+    /// Only used with [`Movement::Line`] in multi line input, moves to the visual line above
+    Up,
+    /// Only used with [`Movement::Line`] in multi line input, moves to the visual line below
+    Down,
 }
 
 pub fn text_input(buffer: RwSignal<String>) -> TextInput {
@@ -121,6 +211,18 @@ pub fn text_input(buffer: RwSignal<String>) -> TextInput {
         cursor_x: 0.0,
         selection: Range { start: 0, end: 0 },
         input_kind: InputKind::SingleLine,
+        is_password: false,
+        rules: Rc::new(EditRules::default()),
+        placeholder: None,
+        placeholder_buf: None,
+        wrap_width: None,
+        preferred_cursor_x: None,
+        ensure_cursor_visible: false,
+        history: EditHistory::new(),
+        text_field: None,
+        ime: Rc::new(RefCell::new(ImeState::default())),
+        composition: None,
+        ime_reset: false,
         clip_start_idx: 0,
         clip_offset_x: 0.0,
         clip_start_x: 0.0,
@@ -140,13 +242,105 @@ enum ClipDirection {
 }
 
 const DEFAULT_FONT_SIZE: f32 = 14.0;
+const PASSWORD_BULLET: char = '•';
 const CURSOR_BLINK_INTERVAL_MS: u64 = 500;
 
 impl TextInput {
-    fn move_cursor(&mut self, move_kind: Movement, direction: Direction) -> bool {
-        if matches!(self.input_kind, InputKind::MultiLine { line_index: _ }) {
-            todo!();
+    /// Turns the input into a multi line editor, which wraps its text and inserts
+    /// a new line on Enter. Put it in a [`scroll`](super::scroll) to limit its height;
+    /// the scroll follows the cursor.
+    ///
+    /// A password input stays on a single line, see [`TextInput::password`].
+    pub fn multi_line(mut self) -> Self {
+        if !self.is_password {
+            self.input_kind = InputKind::MultiLine;
+        }
+        self
+    }
+
+    /// Hides the text behind bullets, and keeps it from being copied.
+    ///
+    /// A password is a single line, whether [`TextInput::multi_line`] is called
+    /// before or after this.
+    pub fn password(mut self) -> Self {
+        self.input_kind = InputKind::SingleLine;
+        self.is_password = true;
+        self
+    }
+
+    /// The most characters the input accepts, longer input is cut off
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.rules_mut().max_length = Some(max_length);
+        self
+    }
+
+    /// Only lets the characters through that `filter` returns true for,
+    /// whether they're typed, pasted or entered with an input method
+    pub fn filter(mut self, filter: impl Fn(char) -> bool + 'static) -> Self {
+        self.rules_mut().filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Rejects the edits that would leave text `validator` returns false for
+    pub fn validator(mut self, validator: impl Fn(&str) -> bool + 'static) -> Self {
+        self.rules_mut().validator = Some(Box::new(validator));
+        self
+    }
+
+    /// The text shown while the buffer is empty
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    fn rules_mut(&mut self) -> &mut EditRules {
+        // the input handler only shares the rules once the input is focused
+        Rc::get_mut(&mut self.rules).expect("edit rules are set before the input is shown")
+    }
+
+    fn is_multi_line(&self) -> bool {
+        matches!(self.input_kind, InputKind::MultiLine)
+    }
+
+    fn is_masked(&self) -> bool {
+        // password input is always a single line
+        self.is_password
+    }
+
+    /// The text that's shown for the buffer
+    fn display_text(&self) -> String {
+        self.buffer.with(|buff| {
+            if self.is_masked() {
+                buff.chars().map(|_| PASSWORD_BULLET).collect()
+            } else {
+                buff.to_string()
+            }
+        })
+    }
+
+    /// Converts an index in the buffer to the index in the shown text
+    fn display_idx(&self, idx: usize) -> usize {
+        if !self.is_masked() {
+            return idx;
         }
+        self.buffer
+            .with(|buff| buff[..idx].chars().count() * PASSWORD_BULLET.len_utf8())
+    }
+
+    /// Converts an index in the shown text to the index in the buffer
+    fn buffer_idx(&self, display_idx: usize) -> usize {
+        if !self.is_masked() {
+            return display_idx;
+        }
+        self.buffer.with(|buff| {
+            buff.char_indices()
+                .nth(display_idx / PASSWORD_BULLET.len_utf8())
+                .map(|(idx, _)| idx)
+                .unwrap_or(buff.len())
+        })
+    }
+
+    fn move_cursor(&mut self, move_kind: Movement, direction: Direction) -> bool {
         match (move_kind, direction) {
             (Movement::Glyph, Direction::Left) => {
                 if self.cursor_glyph_idx >= 1 {
@@ -163,19 +357,33 @@ impl TextInput {
                 false
             }
             (Movement::Line, Direction::Right) => {
-                if self.cursor_glyph_idx < self.buffer.with(|buff| buff.len()) {
-                    self.cursor_glyph_idx = self.buffer.with(|buff| buff.len());
+                let line_end = self.buffer.with(|buff| {
+                    buff[self.cursor_glyph_idx..]
+                        .find('\n')
+                        .map(|idx| self.cursor_glyph_idx + idx)
+                        .unwrap_or(buff.len())
+                });
+                if self.cursor_glyph_idx < line_end {
+                    self.cursor_glyph_idx = line_end;
                     return true;
                 }
                 false
             }
             (Movement::Line, Direction::Left) => {
-                if self.cursor_glyph_idx > 0 {
-                    self.cursor_glyph_idx = 0;
+                let line_start = self.buffer.with(|buff| {
+                    buff[..self.cursor_glyph_idx]
+                        .rfind('\n')
+                        .map(|idx| idx + 1)
+                        .unwrap_or(0)
+                });
+                if self.cursor_glyph_idx > line_start {
+                    self.cursor_glyph_idx = line_start;
                     return true;
                 }
                 false
             }
+            (Movement::Line, Direction::Up) => self.move_cursor_vertically(true),
+            (Movement::Line, Direction::Down) => self.move_cursor_vertically(false),
             (Movement::Word, Direction::Right) => self.buffer.with(|buff| {
                 for (idx, word) in buff.unicode_word_indices() {
                     let word_end_idx = idx + word.len();
@@ -214,17 +422,58 @@ impl TextInput {
             || self.font_style != cx.font_style
     }
 
+    /// Moves the cursor to the visual line above or below, keeping it as close as
+    /// possible to the x it had when it started moving between lines
+    fn move_cursor_vertically(&mut self, up: bool) -> bool {
+        if !self.is_multi_line() {
+            return false;
+        }
+        if self.text_buf.is_none() {
+            return false;
+        }
+
+        let (cursor_x, line_top, line_bottom) =
+            self.multi_line_cursor_position(self.cursor_glyph_idx);
+        let x = *self.preferred_cursor_x.get_or_insert(cursor_x);
+        let half_line = (line_bottom - line_top) / 2.0;
+        let text_buf = self.text_buf.as_ref().unwrap();
+
+        let new_idx = if up && line_top - half_line < 0.0 {
+            0
+        } else if !up && line_bottom + half_line > text_buf.size().height {
+            self.buffer.with(|buff| buff.len())
+        } else {
+            let y = if up {
+                line_top - half_line
+            } else {
+                line_bottom + half_line
+            };
+            let hit = text_buf.hit_point(Point::new(x, y));
+            self.buffer
+                .with(|buff| line_start_idx(buff, hit.line) + hit.index)
+        };
+
+        if new_idx == self.cursor_glyph_idx {
+            return false;
+        }
+        self.cursor_glyph_idx = new_idx;
+        true
+    }
+
     fn get_line_idx(&self) -> usize {
         match self.input_kind {
             InputKind::SingleLine => 0,
-            InputKind::MultiLine { line_index: _ } => todo!(),
+            InputKind::MultiLine => self
+                .buffer
+                .with(|buff| buff[..self.cursor_glyph_idx].matches('\n').count()),
         }
     }
 
     fn clip_text(&mut self, node_layout: &Layout) {
         let virt_text = self.text_buf.as_ref().unwrap();
         let node_width = node_layout.size.width as f64;
-        let cursor_text_loc = Cursor::new(self.get_line_idx(), self.cursor_glyph_idx);
+        let cursor_text_loc =
+            Cursor::new(self.get_line_idx(), self.display_idx(self.cursor_glyph_idx));
         let layout_cursor = virt_text.layout_cursor(&cursor_text_loc);
         let cursor_glyph_pos = virt_text.hit_position(layout_cursor.glyph);
         let cursor_x = cursor_glyph_pos.point.x;
@@ -250,13 +499,7 @@ impl TextInput {
             .hit_point(Point::new(clip_start_x + node_width, 0.0))
             .index;
 
-        let new_text = self
-            .buffer
-            .get()
-            .chars()
-            .skip(clip_start)
-            .take(clip_end - clip_start)
-            .collect();
+        let new_text = self.display_text()[clip_start..clip_end].to_string();
 
         self.cursor_x -= clip_start_x;
         self.clip_start_idx = clip_start;
@@ -294,39 +537,113 @@ impl TextInput {
     }
 
     fn get_selection_rect(&self, node_layout: &Layout) -> Rect {
-        if self.selection == (0..0) {
+        if self.selection.is_empty() {
             return Rect::ZERO;
         }
         let virtual_text = self.text_buf.as_ref().unwrap();
         let text_height = virtual_text.size().height;
-        let selection_start_x = virtual_text.hit_position(self.selection.start).point.x;
-        let selection_end_x = virtual_text.hit_position(self.selection.end + 1).point.x;
+        let selection_start_x = virtual_text
+            .hit_position(self.display_idx(self.selection.start))
+            .point
+            .x;
+        let selection_end_x = virtual_text
+            .hit_position(self.display_idx(self.selection.end))
+            .point
+            .x;
 
         let node_location = node_layout.location;
 
-        let selection_start = Point::new(
-            selection_start_x + node_location.x as f64,
+        Rect::new(
+            selection_start_x - self.clip_start_x + node_location.x as f64,
             node_location.y as f64,
-        );
-
-        Rect::from_points(
-            selection_start,
-            Point::new(
-                selection_start.x + selection_end_x - self.clip_start_x,
-                selection_start.y + text_height,
-            ),
+            selection_end_x - self.clip_start_x + node_location.x as f64,
+            node_location.y as f64 + text_height,
         )
     }
 
+    /// The x of the cursor at `idx`, with the top and bottom of its visual line,
+    /// relative to the text of a multi line input
+    fn multi_line_cursor_position(&self, idx: usize) -> (f64, f64, f64) {
+        let text_buf = self.text_buf.as_ref().unwrap();
+        let hit = text_buf.hit_position(idx);
+        let (ascent, descent) = if hit.glyph_ascent + hit.glyph_descent > 0.0 {
+            (hit.glyph_ascent, hit.glyph_descent)
+        } else {
+            // nothing to measure in an empty buffer
+            (self.font_size as f64, 0.0)
+        };
+
+        let (line, col) = self.buffer.with(|buff| {
+            let line = buff[..idx].matches('\n').count();
+            (line, idx - line_start_idx(buff, line))
+        });
+        if col == 0 && line > 0 {
+            // an empty line has no glyph to hit, which would put the cursor
+            // at the end of the line before it
+            if let Some(run) = text_buf.layout_runs().find(|run| run.line_i == line) {
+                let y = run.line_y as f64;
+                return (0.0, y - ascent, y + descent);
+            }
+        }
+        (hit.point.x, hit.point.y - ascent, hit.point.y + descent)
+    }
+
+    fn get_multi_line_cursor_rect(&self) -> Rect {
+        let (x, top, bottom) = self.multi_line_cursor_position(self.cursor_glyph_idx);
+        Rect::new(x, top, x + self.cursor_width, bottom)
+    }
+
+    /// A range of a multi line input, as one rect for each of its first and last
+    /// lines, and one for all the lines in between
+    fn get_multi_line_range_rects(&self, range: &Range<usize>) -> Vec<Rect> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+        let width = self.text_buf.as_ref().unwrap().size().width;
+        let (start_x, start_top, start_bottom) = self.multi_line_cursor_position(range.start);
+        let (end_x, end_top, end_bottom) = self.multi_line_cursor_position(range.end);
+
+        if start_top == end_top {
+            return vec![Rect::new(start_x, start_top, end_x, end_bottom)];
+        }
+        let mut rects = vec![Rect::new(start_x, start_top, width, start_bottom)];
+        if end_top > start_bottom {
+            rects.push(Rect::new(0.0, start_bottom, width, end_top));
+        }
+        rects.push(Rect::new(0.0, end_top, end_x, end_bottom));
+        rects
+    }
+
     fn update_text_layout(&mut self) {
         let mut text_layout = TextLayout::new();
         let attrs = self.get_text_attrs();
 
-        self.buffer
-            .with(|buff| text_layout.set_text(buff, attrs.clone()));
+        text_layout.set_text(&self.display_text(), attrs.clone());
+
+        self.placeholder_buf = self.placeholder.as_ref().map(|placeholder| {
+            let color = self.color.unwrap_or(Color::BLACK);
+            let color = Color {
+                a: color.a / 2,
+                ..color
+            };
+            let mut placeholder_layout = TextLayout::new();
+            placeholder_layout.set_text(placeholder, self.text_attrs(color));
+            placeholder_layout
+        });
 
-        self.width = 10.0 * self.font_size;
-        self.height = self.font_size;
+        match self.input_kind {
+            InputKind::SingleLine => {
+                self.width = 10.0 * self.font_size;
+                self.height = self.font_size;
+            }
+            InputKind::MultiLine => {
+                if let Some(wrap_width) = self.wrap_width {
+                    text_layout.set_size(wrap_width, f32::MAX);
+                }
+                self.width = self.wrap_width.unwrap_or(10.0 * self.font_size);
+                self.height = (text_layout.size().height as f32).max(self.font_size);
+            }
+        }
 
         // main buff should always get updated
         self.text_buf = Some(text_layout.clone());
@@ -340,7 +657,11 @@ impl TextInput {
     }
 
     pub fn get_text_attrs(&self) -> AttrsList {
-        let mut attrs = Attrs::new().color(self.color.unwrap_or(Color::BLACK));
+        self.text_attrs(self.color.unwrap_or(Color::BLACK))
+    }
+
+    fn text_attrs(&self, color: Color) -> AttrsList {
+        let mut attrs = Attrs::new().color(color);
 
         attrs = attrs.font_size(self.font_size);
 
@@ -368,46 +689,313 @@ impl TextInput {
         self.selection = 0..self.buffer.with(|val| val.len());
     }
 
+    /// Replaces the selection, if any, with `text` and puts the cursor after it.
+    /// The characters the edit rules reject are left out.
+    fn insert_text(&mut self, text: &str) -> bool {
+        let selection = self.selection.clone();
+        let replaced = if selection.is_empty() {
+            self.cursor_glyph_idx..self.cursor_glyph_idx
+        } else {
+            selection.clone()
+        };
+        let text = self
+            .buffer
+            .with(|buff| self.rules.accepted_text(buff, replaced, text));
+        if text.is_empty() && selection.is_empty() {
+            return false;
+        }
+
+        if self.edit(replaced.clone(), &text) {
+            self.cursor_glyph_idx = replaced.start + text.len();
+            self.selection = 0..0;
+        }
+        true
+    }
+
+    /// Replaces `range` of the buffer with `text`, unless the validator rejects
+    /// the text that would result, which leaves the buffer as it is
+    fn edit(&mut self, range: Range<usize>, text: &str) -> bool {
+        let mut new_text = self.buffer.get_untracked();
+        if range.is_empty() {
+            new_text.insert_str(range.start, text);
+        } else {
+            replace_range(&mut new_text, range, Some(text));
+        }
+        if !self.rules.is_valid(&new_text) {
+            return false;
+        }
+        self.buffer.set(new_text);
+        true
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        if self.selection.is_empty() || self.is_password {
+            return None;
+        }
+        self.buffer
+            .with(|buff| buff.get(self.selection.clone()).map(|s| s.to_string()))
+    }
+
+    fn copy(&mut self) -> bool {
+        match self.selected_text() {
+            Some(text) => {
+                AppContext::with_clipboard(|clipboard| clipboard.put_string(&text));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn cut(&mut self) -> bool {
+        let text = match self.selected_text() {
+            Some(text) => text,
+            None => return false,
+        };
+        let selection = self.selection.clone();
+        if self.edit(selection.clone(), "") {
+            AppContext::with_clipboard(|clipboard| clipboard.put_string(&text));
+            self.cursor_glyph_idx = selection.start;
+            self.selection = 0..0;
+        }
+        true
+    }
+
+    fn paste(&mut self) -> bool {
+        let text = match AppContext::with_clipboard(|clipboard| clipboard.get_string()) {
+            Some(text) => text.replace('\r', ""),
+            None => return false,
+        };
+        let text = match self.input_kind {
+            // a single line can't hold the line breaks
+            InputKind::SingleLine => text.replace('\n', " "),
+            InputKind::MultiLine => text,
+        };
+        if text.is_empty() {
+            return false;
+        }
+        self.insert_text(&text)
+    }
+
+    /// Runs a cursor movement, and when `extend` is set, extends the selection
+    /// from its other end to the new cursor
+    fn move_selecting(&mut self, extend: bool, movement: impl FnOnce(&mut Self) -> bool) -> bool {
+        if !extend {
+            self.selection = 0..0;
+            return movement(self);
+        }
+
+        let anchor = if self.selection.is_empty() {
+            self.cursor_glyph_idx
+        } else if self.cursor_glyph_idx == self.selection.start {
+            self.selection.end
+        } else {
+            self.selection.start
+        };
+        let moved = movement(self);
+        let cursor = self.cursor_glyph_idx;
+        self.selection = anchor.min(cursor)..anchor.max(cursor);
+        moved
+    }
+
+    fn snapshot(&self) -> TextInputState {
+        TextInputState {
+            text: self.buffer.get_untracked(),
+            cursor: self.cursor_glyph_idx,
+            selection: self.selection.clone(),
+        }
+    }
+
+    fn restore(&mut self, state: TextInputState) {
+        self.buffer.set(state.text);
+        self.cursor_glyph_idx = state.cursor;
+        self.selection = state.selection;
+    }
+
+    fn undo(&mut self) -> bool {
+        match self.history.undo(self.snapshot()) {
+            Some(state) => {
+                self.restore(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn redo(&mut self) -> bool {
+        match self.history.redo(self.snapshot()) {
+            Some(state) => {
+                self.restore(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Registers the input with the window's input method when it gains focus,
+    /// and unregisters it when it loses focus
+    fn update_text_field(&mut self, app_state: &mut AppState) {
+        // passwords are typed without an input method, which could show them
+        if !app_state.input_method || self.is_password {
+            return;
+        }
+        let is_focused = app_state.is_focused(&self.id);
+        match self.text_field {
+            None if is_focused => {
+                let (id, buffer, ime) = (self.id, self.buffer, self.ime.clone());
+                let rules = self.rules.clone();
+                let multi_line = self.is_multi_line();
+                let token = app_state.add_text_field(
+                    id,
+                    Box::new(move || {
+                        Box::new(TextInputHandler {
+                            id,
+                            buffer,
+                            ime: ime.clone(),
+                            rules: rules.clone(),
+                            multi_line,
+                        })
+                    }),
+                );
+                app_state.set_focused_text_field(Some(token));
+                self.text_field = Some(token);
+            }
+            Some(token) if !is_focused => {
+                app_state.remove_text_field(token);
+                self.text_field = None;
+                self.composition = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Picks up the edits the input method made through the input handler
+    fn apply_ime_changes(&mut self) {
+        let mut ime = self.ime.borrow_mut();
+        if !std::mem::take(&mut ime.changed) {
+            return;
+        }
+        let len = self.buffer.with(|buff| buff.len());
+        let anchor = ime.anchor.min(len);
+        self.cursor_glyph_idx = ime.active.min(len);
+        self.selection = anchor.min(self.cursor_glyph_idx)..anchor.max(self.cursor_glyph_idx);
+        self.composition = ime
+            .composition
+            .clone()
+            .filter(|range| !range.is_empty() && range.end <= len);
+        let edits = std::mem::take(&mut ime.edits);
+        if edits.is_empty() {
+            // the input method only moved the selection, so the next edit is a new transaction
+            self.history.end_group();
+        }
+        for before in edits {
+            // composed text is typed like any other, so it's coalesced with the typing around it
+            self.history.record(before, EditKind::Insert);
+        }
+        self.last_cursor_action_on = Instant::now();
+        self.ensure_cursor_visible = self.is_multi_line();
+    }
+
+    /// Shares the text, selection and position of the input with the input method
+    fn sync_ime(&mut self, app_state: &mut AppState, text_origin: Point) {
+        let token = match self.text_field {
+            Some(token) if app_state.focused_text_field == Some(token) => token,
+            _ => return,
+        };
+        let anchor = if self.selection.is_empty() {
+            self.cursor_glyph_idx
+        } else if self.cursor_glyph_idx == self.selection.start {
+            self.selection.end
+        } else {
+            self.selection.start
+        };
+
+        let mut ime = self.ime.borrow_mut();
+        ime.anchor = anchor;
+        ime.active = self.cursor_glyph_idx;
+        ime.composition = self.composition.clone();
+        ime.text_layout = self.text_buf.clone();
+        ime.scale = app_state.scale;
+        let moved = ime.text_origin != text_origin;
+        ime.text_origin = text_origin;
+        drop(ime);
+
+        if std::mem::take(&mut self.ime_reset) {
+            app_state
+                .handle
+                .update_text_field(token, glazier::text::Event::Reset);
+        } else if moved {
+            app_state
+                .handle
+                .update_text_field(token, glazier::text::Event::LayoutChanged);
+        }
+    }
+
+    /// Handles the key, recording the edit it made, if any, in the history
     fn handle_key_down(&mut self, cx: &mut EventCx<'_>, event: &glazier::KeyEvent) -> bool {
+        if self.composition.is_some() {
+            // the input method uses the keys while composing
+            return false;
+        }
+        let command = command_modifier();
+        if let Key::Character(ch) = &event.key {
+            if ch.eq_ignore_ascii_case("z") {
+                if event.mods == command {
+                    return self.undo();
+                } else if event.mods == command | Modifiers::SHIFT {
+                    return self.redo();
+                }
+            }
+        }
+
+        let before = self.snapshot();
+        let handled = self.apply_key_down(cx, event);
+        if self.buffer.with(|buff| *buff != before.text) {
+            let kind = match event.key {
+                Key::Character(_) if !event.mods.contains(command) => EditKind::Insert,
+                Key::Backspace | Key::Delete => EditKind::Delete,
+                _ => EditKind::Other,
+            };
+            self.history.record(before, kind);
+        } else if handled {
+            // the cursor or the selection moved, so the next edit is a new transaction
+            self.history.end_group();
+        }
+        handled
+    }
+
+    fn apply_key_down(&mut self, cx: &mut EventCx<'_>, event: &glazier::KeyEvent) -> bool {
+        // only moving between lines keeps the x the cursor started at
+        let preferred_cursor_x = self.preferred_cursor_x.take();
+        let extend = event.mods.shift();
+
         match event.key {
             Key::Character(ref ch) => {
-                let handled_modifier_command = !event.mods.is_empty()
-                    && match (event.mods, ch.as_str(), cfg!(target_os = "macos")) {
-                        (Modifiers::CONTROL, "a", false) => {
+                if event.mods == command_modifier() {
+                    return match ch.as_str() {
+                        "a" => {
                             self.select_all();
                             true
                         }
-                        (Modifiers::META, "a", true) => {
-                            self.select_all();
-                            true
-                        }
-                        _ => {
-                            self.selection = 0..0;
-                            false
-                        }
+                        "c" => self.copy(),
+                        "x" => self.cut(),
+                        "v" => self.paste(),
+                        _ => false,
                     };
-
-                if handled_modifier_command {
-                    return true;
                 }
-                let selection = self.selection.clone();
-                if selection != (0..0) {
-                    self.buffer
-                        .update(|buf| replace_range(buf, selection.clone(), None));
-                    self.cursor_glyph_idx = selection.start;
-                    self.selection = 0..0;
+                if self.text_field.is_some() {
+                    // typed through the input method, which commits the text with the input handler
+                    return false;
                 }
-
-                self.buffer
-                    .update(|buf| buf.insert_str(self.cursor_glyph_idx, &ch.clone()));
-                self.move_cursor(Movement::Glyph, Direction::Right)
+                self.insert_text(ch)
             }
+            Key::Enter if self.is_multi_line() => self.insert_text("\n"),
             Key::Backspace => {
                 let selection = self.selection.clone();
-                if selection != (0..0) {
-                    self.buffer
-                        .update(|buf| replace_range(buf, selection, None));
-                    self.cursor_glyph_idx = 0;
+                if !selection.is_empty() {
+                    if self.edit(selection.clone(), "") {
+                        self.cursor_glyph_idx = selection.start;
+                        self.selection = 0..0;
+                    }
                     true
                 } else {
                     let prev_cursor_idx = self.cursor_glyph_idx;
@@ -422,9 +1010,9 @@ impl TextInput {
                         return false;
                     }
 
-                    self.buffer.update(|buf| {
-                        replace_range(buf, self.cursor_glyph_idx..prev_cursor_idx, None);
-                    });
+                    if !self.edit(self.cursor_glyph_idx..prev_cursor_idx, "") {
+                        self.cursor_glyph_idx = prev_cursor_idx;
+                    }
                     true
                 }
             }
@@ -441,9 +1029,7 @@ impl TextInput {
                     return false;
                 }
 
-                self.buffer.update(|buf| {
-                    replace_range(buf, prev_cursor_idx..self.cursor_glyph_idx, None);
-                });
+                self.edit(prev_cursor_idx..self.cursor_glyph_idx, "");
 
                 // Move cursor to the range to delete, delete it and move cursor back
                 // TODO: extract moving to next word logic as a method and use it here instead
@@ -454,28 +1040,51 @@ impl TextInput {
                 cx.app_state.clear_focus();
                 true
             }
-            Key::End => self.move_cursor(Movement::Line, Direction::Right),
-            Key::Home => self.move_cursor(Movement::Line, Direction::Left),
+            Key::End => self.move_selecting(extend, |input| {
+                input.move_cursor(Movement::Line, Direction::Right)
+            }),
+            Key::Home => self.move_selecting(extend, |input| {
+                input.move_cursor(Movement::Line, Direction::Left)
+            }),
+            Key::ArrowUp | Key::ArrowDown if self.is_multi_line() => {
+                self.preferred_cursor_x = preferred_cursor_x;
+                let direction = if event.key == Key::ArrowUp {
+                    Direction::Up
+                } else {
+                    Direction::Down
+                };
+                self.move_selecting(extend, |input| input.move_cursor(Movement::Line, direction))
+            }
             Key::ArrowLeft => {
-                if !self.selection.is_empty() {
+                let movement = if event.mods.ctrl() {
+                    Movement::Word
+                } else {
+                    Movement::Glyph
+                };
+                if !extend && !self.selection.is_empty() {
                     self.cursor_glyph_idx = self.selection.start;
                     self.selection = 0..0;
                     true
-                } else if event.mods.ctrl() {
-                    self.move_cursor(Movement::Word, Direction::Left)
                 } else {
-                    self.move_cursor(Movement::Glyph, Direction::Left)
+                    self.move_selecting(extend, |input| {
+                        input.move_cursor(movement, Direction::Left)
+                    })
                 }
             }
             Key::ArrowRight => {
-                if !self.selection.is_empty() {
+                let movement = if event.mods.ctrl() {
+                    Movement::Word
+                } else {
+                    Movement::Glyph
+                };
+                if !extend && !self.selection.is_empty() {
                     self.cursor_glyph_idx = self.selection.end;
                     self.selection = 0..0;
                     true
-                } else if event.mods.ctrl() {
-                    self.move_cursor(Movement::Word, Direction::Right)
                 } else {
-                    self.move_cursor(Movement::Glyph, Direction::Right)
+                    self.move_selecting(extend, |input| {
+                        input.move_cursor(movement, Direction::Right)
+                    })
                 }
             }
             _ => {
@@ -486,6 +1095,20 @@ impl TextInput {
     }
 }
 
+/// The modifier of shortcuts like copy and undo on this platform
+fn command_modifier() -> Modifiers {
+    if cfg!(target_os = "macos") {
+        Modifiers::META
+    } else {
+        Modifiers::CONTROL
+    }
+}
+
+/// The index in `buff` where its line `line` starts
+fn line_start_idx(buff: &str, line: usize) -> usize {
+    buff.split('\n').take(line).map(|line| line.len() + 1).sum()
+}
+
 fn replace_range(buff: &mut String, del_range: Range<usize>, replacement: Option<&str>) {
     assert!(del_range.start < del_range.end);
     // Get text after range to delete
@@ -501,6 +1124,167 @@ fn replace_range(buff: &mut String, del_range: Range<usize>, replacement: Option
     buff.push_str(&after_del_range);
 }
 
+/// Lets the window's input method read and edit a [`TextInput`]. The edits go
+/// straight to the buffer, the input picks up the rest of the state the next
+/// time it updates.
+struct TextInputHandler {
+    id: Id,
+    buffer: RwSignal<String>,
+    ime: Rc<RefCell<ImeState>>,
+    rules: Rc<EditRules>,
+    multi_line: bool,
+}
+
+impl TextInputHandler {
+    fn changed(&self) {
+        self.ime.borrow_mut().changed = true;
+        self.id.request_layout();
+    }
+
+    /// Converts a point in the window to one relative to the text
+    fn to_text_point(&self, point: Point) -> Point {
+        let ime = self.ime.borrow();
+        Point::new(point.x / ime.scale, point.y / ime.scale) - ime.text_origin.to_vec2()
+    }
+
+    /// Converts a rect relative to the text to one in the window
+    fn to_window_rect(&self, rect: Rect) -> Rect {
+        let ime = self.ime.borrow();
+        let rect = rect + ime.text_origin.to_vec2();
+        Rect::new(
+            rect.x0 * ime.scale,
+            rect.y0 * ime.scale,
+            rect.x1 * ime.scale,
+            rect.y1 * ime.scale,
+        )
+    }
+}
+
+impl InputHandler for TextInputHandler {
+    fn selection(&self) -> Selection {
+        let ime = self.ime.borrow();
+        Selection::new(ime.anchor, ime.active)
+    }
+
+    fn set_selection(&mut self, selection: Selection) {
+        {
+            let mut ime = self.ime.borrow_mut();
+            ime.anchor = selection.anchor;
+            ime.active = selection.active;
+        }
+        self.changed();
+    }
+
+    fn composition_range(&self) -> Option<Range<usize>> {
+        self.ime.borrow().composition.clone()
+    }
+
+    fn set_composition_range(&mut self, range: Option<Range<usize>>) {
+        self.ime.borrow_mut().composition = range;
+        self.changed();
+    }
+
+    fn is_char_boundary(&self, i: usize) -> bool {
+        self.buffer.with(|buff| buff.is_char_boundary(i))
+    }
+
+    fn len(&self) -> usize {
+        self.buffer.with(|buff| buff.len())
+    }
+
+    fn slice(&self, range: Range<usize>) -> Cow<str> {
+        self.buffer.with(|buff| buff[range].to_string()).into()
+    }
+
+    fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        let text = if self.multi_line {
+            text.to_string()
+        } else {
+            text.replace('\n', " ")
+        };
+        let text = self
+            .buffer
+            .with(|buff| self.rules.accepted_text(buff, range.clone(), &text));
+        let mut new_text = self.buffer.get_untracked();
+        new_text.replace_range(range.clone(), &text);
+        if !self.rules.is_valid(&new_text) {
+            return;
+        }
+        let before = {
+            let ime = self.ime.borrow();
+            TextInputState {
+                text: self.buffer.get_untracked(),
+                cursor: ime.active,
+                selection: ime.anchor.min(ime.active)..ime.anchor.max(ime.active),
+            }
+        };
+        self.buffer.set(new_text);
+        {
+            let mut ime = self.ime.borrow_mut();
+            ime.edits.push(before);
+            ime.anchor = range.start + text.len();
+            ime.active = ime.anchor;
+        }
+        self.changed();
+    }
+
+    fn hit_test_point(&self, point: Point) -> HitTestPoint {
+        let point = self.to_text_point(point);
+        let mut hit = HitTestPoint::default();
+        let ime = self.ime.borrow();
+        if let Some(text_layout) = ime.text_layout.as_ref() {
+            let cursor = text_layout.hit_point(point);
+            hit.idx = self
+                .buffer
+                .with(|buff| line_start_idx(buff, cursor.line) + cursor.index);
+            hit.is_inside = text_layout.size().to_rect().contains(point);
+        }
+        hit
+    }
+
+    fn line_range(&self, index: usize, _affinity: Affinity) -> Range<usize> {
+        self.buffer.with(|buff| {
+            let start = buff[..index].rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+            let end = buff[index..]
+                .find('\n')
+                .map(|idx| index + idx)
+                .unwrap_or(buff.len());
+            start..end
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Rect> {
+        let size = self.ime.borrow().text_layout.as_ref()?.size();
+        Some(self.to_window_rect(size.to_rect()))
+    }
+
+    fn slice_bounding_box(&self, range: Range<usize>) -> Option<Rect> {
+        let rect = {
+            let ime = self.ime.borrow();
+            let text_layout = ime.text_layout.as_ref()?;
+            let start = text_layout.hit_position(range.start);
+            let end = text_layout.hit_position(range.end);
+            let top = start.point.y - start.glyph_ascent;
+            let bottom = end.point.y + end.glyph_descent;
+            if start.line == end.line {
+                Rect::new(start.point.x, top, end.point.x, bottom)
+            } else {
+                Rect::new(0.0, top, text_layout.size().width, bottom)
+            }
+        };
+        Some(self.to_window_rect(rect))
+    }
+
+    fn handle_action(&mut self, action: ImeAction) {
+        if let ImeAction::InsertNewLine { .. } = action {
+            if self.multi_line {
+                let selection = self.selection().range();
+                self.replace_range(selection, "\n");
+            }
+        }
+    }
+}
+
 impl View for TextInput {
     fn id(&self) -> Id {
         self.id
@@ -529,8 +1313,12 @@ impl View for TextInput {
     }
 
     fn event(&mut self, cx: &mut EventCx, _id_path: Option<&[Id]>, event: Event) -> bool {
+        self.apply_ime_changes();
         let is_handled = match &event {
             Event::PointerDown(event) => {
+                self.history.end_group();
+                // clicking commits the text being composed as it is
+                self.composition = None;
                 if !self.is_focused {
                     // Just gained focus - move cursor to buff end
                     self.set_cursor_glyph_idx(self.buffer.with(|buff| buff.len()));
@@ -547,17 +1335,19 @@ impl View for TextInput {
                         taffy::style::LengthPercentage::Points(padding) => padding,
                         taffy::style::LengthPercentage::Percent(pct) => pct * layout.size.height,
                     };
-                    self.cursor_glyph_idx = self
-                        .text_buf
-                        .as_ref()
-                        .unwrap()
-                        .hit_point(Point::new(
-                            event.pos.x + self.clip_start_x - padding_left as f64,
-                            // TODO: prevent cursor incorrectly going to end of buffer when clicking
-                            // slightly below the text
-                            event.pos.y - padding_top as f64,
-                        ))
-                        .index;
+                    let hit = self.text_buf.as_ref().unwrap().hit_point(Point::new(
+                        event.pos.x + self.clip_start_x - padding_left as f64,
+                        // TODO: prevent cursor incorrectly going to end of buffer when clicking
+                        // slightly below the text
+                        event.pos.y - padding_top as f64,
+                    ));
+                    self.cursor_glyph_idx = match self.input_kind {
+                        InputKind::SingleLine => self.buffer_idx(hit.index),
+                        // the index is within the line that was hit
+                        InputKind::MultiLine => self
+                            .buffer
+                            .with(|buff| line_start_idx(buff, hit.line) + hit.index),
+                    };
                 }
                 true
             }
@@ -575,13 +1365,17 @@ impl View for TextInput {
         if is_handled {
             cx.app_state.request_layout(self.id);
             self.last_cursor_action_on = Instant::now();
+            self.ensure_cursor_visible = self.is_multi_line();
+            self.ime_reset = true;
         }
 
-        false
+        // keys the input doesn't handle go on to the input method
+        matches!(event, Event::KeyDown(_)) && is_handled
     }
 
     fn layout(&mut self, cx: &mut crate::context::LayoutCx) -> taffy::prelude::Node {
         cx.layout_node(self.id, true, |cx| {
+            self.apply_ime_changes();
             self.is_focused = cx.app_state.is_focused(&self.id);
             if self.text_layout_changed(cx) {
                 self.font_size = cx.current_font_size().unwrap_or(DEFAULT_FONT_SIZE);
@@ -589,7 +1383,8 @@ impl View for TextInput {
                 self.font_weight = cx.font_weight;
                 self.font_style = cx.font_style;
                 self.update_text_layout();
-            } else if self.text_buf.is_none() {
+            } else if self.text_buf.is_none() || self.is_multi_line() {
+                // the height of multi line input follows the text
                 self.update_text_layout();
             }
 
@@ -614,13 +1409,43 @@ impl View for TextInput {
         })
     }
 
-    fn compute_layout(&mut self, _cx: &mut crate::context::LayoutCx) -> Option<Rect> {
+    fn compute_layout(&mut self, cx: &mut crate::context::LayoutCx) -> Option<Rect> {
+        if self.is_multi_line() {
+            let layout = cx.get_layout(self.id()).unwrap();
+            let style = cx.app_state.get_computed_style(self.id);
+            let padding_left = match style.padding_left {
+                taffy::style::LengthPercentage::Points(padding) => padding,
+                taffy::style::LengthPercentage::Percent(pct) => pct * layout.size.width,
+            };
+            let padding_right = match style.padding_right {
+                taffy::style::LengthPercentage::Points(padding) => padding,
+                taffy::style::LengthPercentage::Percent(pct) => pct * layout.size.width,
+            };
+            let wrap_width = (layout.size.width - padding_left - padding_right).max(0.0);
+            if self.wrap_width != Some(wrap_width) {
+                self.wrap_width = Some(wrap_width);
+                cx.app_state.request_layout(self.id());
+            }
+        }
+
         self.update_text_layout();
+
+        if self.ensure_cursor_visible {
+            self.ensure_cursor_visible = false;
+            let text_node = self.text_node.unwrap();
+            let location = cx.app_state.taffy.layout(text_node).unwrap().location;
+            let cursor_rect =
+                self.get_multi_line_cursor_rect() + Vec2::new(location.x as f64, location.y as f64);
+            scroll_to_visible(self.id, cursor_rect);
+        }
         None
     }
 
     fn paint(&mut self, cx: &mut crate::context::PaintCx) {
-        if !cx.app_state.is_focused(&self.id) && self.buffer.with(|buff| buff.is_empty()) {
+        self.apply_ime_changes();
+        self.update_text_field(cx.app_state);
+        let is_empty = self.buffer.with(|buff| buff.is_empty());
+        if !cx.app_state.is_focused(&self.id) && is_empty && self.placeholder.is_none() {
             return;
         }
 
@@ -657,19 +1482,23 @@ impl View for TextInput {
                         .text_buf
                         .as_ref()
                         .unwrap()
-                        .hit_position(self.cursor_glyph_idx);
+                        .hit_position(self.display_idx(self.cursor_glyph_idx));
                     self.cursor_x = hit_pos.point.x;
                 }
             }
-            InputKind::MultiLine { .. } => {
-                todo!();
+            InputKind::MultiLine => {
+                self.clip_txt_buf = None;
+                self.clip_start_idx = 0;
+                self.clip_start_x = 0.0;
             }
         }
 
         let location = node_layout.location;
         let text_start_point = Point::new(location.x as f64, location.y as f64);
 
-        if let Some(clip_txt) = self.clip_txt_buf.as_mut() {
+        if is_empty && self.placeholder_buf.is_some() {
+            cx.draw_text(self.placeholder_buf.as_ref().unwrap(), text_start_point);
+        } else if let Some(clip_txt) = self.clip_txt_buf.as_mut() {
             cx.draw_text(
                 clip_txt,
                 Point::new(text_start_point.x - self.clip_offset_x, text_start_point.y),
@@ -678,22 +1507,73 @@ impl View for TextInput {
             cx.draw_text(self.text_buf.as_ref().unwrap(), text_start_point);
         }
 
+        let text_color = self.color.unwrap_or(Color::BLACK);
+        if let Some(composition) = self.composition.clone() {
+            // underline the preedit text
+            let underlines = match self.input_kind {
+                InputKind::SingleLine => {
+                    let text_buf = self.text_buf.as_ref().unwrap();
+                    let start_x = text_buf
+                        .hit_position(self.display_idx(composition.start))
+                        .point
+                        .x;
+                    let end_x = text_buf
+                        .hit_position(self.display_idx(composition.end))
+                        .point
+                        .x;
+                    let bottom = text_buf.size().height;
+                    vec![
+                        Rect::new(start_x, bottom - 1.0, end_x, bottom)
+                            + Vec2::new(location.x as f64 - self.clip_start_x, location.y as f64),
+                    ]
+                }
+                InputKind::MultiLine => self
+                    .get_multi_line_range_rects(&composition)
+                    .into_iter()
+                    .map(|rect| {
+                        Rect::new(rect.x0, rect.y1 - 1.0, rect.x1, rect.y1)
+                            + Vec2::new(location.x as f64, location.y as f64)
+                    })
+                    .collect(),
+            };
+            for underline in underlines {
+                cx.fill(&underline, text_color);
+            }
+        }
+
+        let text_origin = cx.app_state.get_layout_rect(self.id).origin()
+            + Vec2::new(location.x as f64 - self.clip_start_x, location.y as f64);
+        self.sync_ime(cx.app_state, text_origin);
+
         let is_cursor_visible = cx.app_state.is_focused(&self.id)
             && (self.last_cursor_action_on.elapsed().as_millis()
                 / CURSOR_BLINK_INTERVAL_MS as u128)
                 % 2
                 == 0;
 
+        let text_offset = Vec2::new(location.x as f64, location.y as f64);
         if is_cursor_visible {
-            let cursor_rect = self.get_cursor_rect(&node_layout);
+            let cursor_rect = match self.input_kind {
+                InputKind::SingleLine => self.get_cursor_rect(&node_layout),
+                InputKind::MultiLine => self.get_multi_line_cursor_rect() + text_offset,
+            };
             cx.fill(&cursor_rect, cursor_color.unwrap_or(Color::BLACK));
         }
         if cx.app_state.is_focused(&self.id) {
-            let selection_rect = self.get_selection_rect(&node_layout);
-            cx.fill(
-                &selection_rect,
-                cursor_color.unwrap_or(Color::rgba8(0, 0, 0, 150)),
-            );
+            let selection_rects = match self.input_kind {
+                InputKind::SingleLine => vec![self.get_selection_rect(&node_layout)],
+                InputKind::MultiLine => self
+                    .get_multi_line_range_rects(&self.selection)
+                    .into_iter()
+                    .map(|rect| rect + text_offset)
+                    .collect(),
+            };
+            for selection_rect in selection_rects {
+                cx.fill(
+                    &selection_rect,
+                    cursor_color.unwrap_or(Color::rgba8(0, 0, 0, 150)),
+                );
+            }
         } else {
             self.selection = 0..0;
         }
@@ -706,11 +1586,45 @@ impl View for TextInput {
             }),
         );
     }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        let mut node = NodeBuilder::new(Role::TextField);
+        node.set_value(self.display_text());
+        if self.is_password {
+            node.set_protected();
+        }
+        if let Some(placeholder) = self.placeholder.clone() {
+            node.set_placeholder(placeholder);
+        }
+        if self.is_multi_line() {
+            node.set_multiline();
+        }
+        Some(node)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::replace_range;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use accesskit::Role;
+    use glazier::{
+        text::{Affinity, InputHandler},
+        KbKey, Modifiers,
+    };
+    use leptos_reactive::{create_effect, create_rw_signal, SignalGet, SignalGetUntracked};
+
+    use crate::{
+        clipboard::MemoryClipboard, id::Id, test_harness::TestHarness, views::label, AppContext,
+    };
+
+    use super::{
+        command_modifier, line_start_idx, replace_range, text_input, EditRules, ImeState,
+        TextInputHandler,
+    };
 
     #[test]
     fn replace_range_start() {
@@ -757,4 +1671,259 @@ mod tests {
         replace_range(&mut s, 6..len, None);
         assert_eq!("Sample", s);
     }
+
+    #[test]
+    fn line_start() {
+        let s = "first\n\nthird";
+        assert_eq!(line_start_idx(s, 0), 0);
+        assert_eq!(line_start_idx(s, 1), 6);
+        assert_eq!(line_start_idx(s, 2), 7);
+    }
+
+    #[test]
+    fn multi_line_enter_and_move_up() {
+        let buffer = Rc::new(Cell::new(None));
+        let input_buffer = buffer.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let text = create_rw_signal(cx.scope, "aa".to_string());
+                input_buffer.set(Some(text));
+                text_input(text).multi_line()
+            },
+            (200.0, 200.0),
+        );
+        let buffer = buffer.get().unwrap();
+
+        // focusing puts the cursor at the end
+        harness.click((5.0, 5.0));
+        harness.key_down(KbKey::Enter, Modifiers::empty());
+        harness.key_down(KbKey::Character("a".to_string()), Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "aa\na");
+
+        // the cursor keeps its x, which is after the first character of the line above
+        harness.key_down(KbKey::ArrowUp, Modifiers::empty());
+        harness.key_down(KbKey::Character("X".to_string()), Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "aXa\na");
+
+        harness.key_down(KbKey::Home, Modifiers::empty());
+        harness.key_down(KbKey::End, Modifiers::SHIFT);
+        harness.key_down(KbKey::Backspace, Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "\na");
+    }
+
+    #[test]
+    fn cut_and_paste() {
+        AppContext::set_clipboard(MemoryClipboard::default());
+        let buffer = Rc::new(Cell::new(None));
+        let input_buffer = buffer.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let text = create_rw_signal(cx.scope, "hello".to_string());
+                input_buffer.set(Some(text));
+                text_input(text)
+            },
+            (200.0, 200.0),
+        );
+        let buffer = buffer.get().unwrap();
+        let command = command_modifier();
+
+        harness.click((5.0, 5.0));
+        harness.key_down(KbKey::Character("a".to_string()), command);
+        harness.key_down(KbKey::Character("x".to_string()), command);
+        assert_eq!(buffer.get_untracked(), "");
+
+        harness.key_down(KbKey::Character("v".to_string()), command);
+        harness.key_down(KbKey::Character("v".to_string()), command);
+        assert_eq!(buffer.get_untracked(), "hellohello");
+        assert_eq!(
+            AppContext::with_clipboard(|clipboard| clipboard.get_string()),
+            Some("hello".to_string())
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_coalesced_edits() {
+        let buffer = Rc::new(Cell::new(None));
+        let input_buffer = buffer.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let text = create_rw_signal(cx.scope, "".to_string());
+                input_buffer.set(Some(text));
+                text_input(text)
+            },
+            (200.0, 200.0),
+        );
+        let buffer = buffer.get().unwrap();
+        let command = command_modifier();
+        let type_char = |harness: &mut TestHarness<_>, ch: &str, mods: Modifiers| {
+            harness.key_down(KbKey::Character(ch.to_string()), mods);
+        };
+
+        harness.click((5.0, 5.0));
+        type_char(&mut harness, "a", Modifiers::empty());
+        type_char(&mut harness, "b", Modifiers::empty());
+        harness.key_down(KbKey::Backspace, Modifiers::empty());
+        type_char(&mut harness, "c", Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "ac");
+
+        type_char(&mut harness, "z", command);
+        assert_eq!(buffer.get_untracked(), "a");
+        type_char(&mut harness, "z", command);
+        assert_eq!(buffer.get_untracked(), "ab");
+        type_char(&mut harness, "z", command);
+        assert_eq!(buffer.get_untracked(), "");
+        type_char(&mut harness, "Z", command | Modifiers::SHIFT);
+        assert_eq!(buffer.get_untracked(), "ab");
+
+        // undo brings back the selection the edit replaced
+        type_char(&mut harness, "a", command);
+        type_char(&mut harness, "x", Modifiers::empty());
+        type_char(&mut harness, "z", command);
+        assert_eq!(buffer.get_untracked(), "ab");
+        type_char(&mut harness, "y", Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "y");
+    }
+
+    #[test]
+    fn input_handler_composes_and_commits() {
+        let harness = TestHarness::new(|| label(String::new), (100.0, 100.0));
+        let buffer = create_rw_signal(harness.scope(), "ab".to_string());
+        let ime = Rc::new(RefCell::new(ImeState {
+            anchor: 2,
+            active: 2,
+            scale: 1.0,
+            ..Default::default()
+        }));
+        let mut handler = TextInputHandler {
+            id: Id::next(),
+            buffer,
+            ime: ime.clone(),
+            rules: Rc::new(EditRules::default()),
+            multi_line: false,
+        };
+
+        // the preedit text is part of the buffer while composing
+        handler.replace_range(2..2, "ni");
+        handler.set_composition_range(Some(2..4));
+        assert_eq!(buffer.get_untracked(), "abni");
+        assert_eq!(handler.composition_range(), Some(2..4));
+
+        handler.replace_range(2..4, "你");
+        handler.set_composition_range(None);
+        assert_eq!(buffer.get_untracked(), "ab你");
+        assert_eq!(handler.selection().active, 5);
+        assert!(!handler.is_char_boundary(3));
+        assert_eq!(handler.line_range(1, Affinity::Downstream), 0..5);
+        assert!(ime.borrow().changed);
+
+        // the input records both edits in its history, starting from before the preedit text
+        let ime = ime.borrow();
+        let texts: Vec<&str> = ime.edits.iter().map(|state| state.text.as_str()).collect();
+        assert_eq!(texts, ["ab", "abni"]);
+        assert_eq!(ime.edits[0].cursor, 2);
+    }
+
+    #[test]
+    fn edit_rules_reject_characters_and_edits() {
+        let buffer = Rc::new(Cell::new(None));
+        let input_buffer = buffer.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let text = create_rw_signal(cx.scope, "".to_string());
+                input_buffer.set(Some(text));
+                text_input(text)
+                    .max_length(4)
+                    .filter(|ch| ch.is_ascii_digit())
+                    .validator(|text| !text.starts_with('0'))
+            },
+            (200.0, 200.0),
+        );
+        let buffer = buffer.get().unwrap();
+
+        harness.click((5.0, 5.0));
+        for ch in ["1", "a", "0", "2", "3", "4"] {
+            harness.key_down(KbKey::Character(ch.to_string()), Modifiers::empty());
+        }
+        assert_eq!(buffer.get_untracked(), "1023");
+
+        // rejected edits never reach the buffer
+        let texts = Rc::new(RefCell::new(Vec::new()));
+        let effect_texts = texts.clone();
+        create_effect(harness.scope(), move |_| {
+            effect_texts.borrow_mut().push(buffer.get())
+        });
+
+        // deleting the 1 would leave a leading 0
+        harness.key_down(KbKey::Home, Modifiers::empty());
+        harness.key_down(KbKey::Delete, Modifiers::empty());
+        assert_eq!(buffer.get_untracked(), "1023");
+        assert_eq!(*texts.borrow(), ["1023"]);
+    }
+
+    #[test]
+    fn password_is_not_multi_line() {
+        for password_first in [true, false] {
+            let buffer = Rc::new(Cell::new(None));
+            let input_buffer = buffer.clone();
+            let mut harness = TestHarness::new(
+                move || {
+                    let cx = AppContext::get_current();
+                    let text = create_rw_signal(cx.scope, "secret".to_string());
+                    input_buffer.set(Some(text));
+                    if password_first {
+                        text_input(text).password().multi_line()
+                    } else {
+                        text_input(text).multi_line().password()
+                    }
+                },
+                (200.0, 200.0),
+            );
+            let buffer = buffer.get().unwrap();
+
+            harness.click((5.0, 5.0));
+            harness.key_down(KbKey::Enter, Modifiers::empty());
+            assert_eq!(buffer.get_untracked(), "secret");
+        }
+    }
+
+    #[test]
+    fn password_is_masked_and_not_copied() {
+        AppContext::set_clipboard(MemoryClipboard::default());
+        let buffer = Rc::new(Cell::new(None));
+        let input_buffer = buffer.clone();
+        let mut harness = TestHarness::new(
+            move || {
+                let cx = AppContext::get_current();
+                let text = create_rw_signal(cx.scope, "secret".to_string());
+                input_buffer.set(Some(text));
+                text_input(text).password().placeholder("Password")
+            },
+            (200.0, 200.0),
+        );
+        let buffer = buffer.get().unwrap();
+        let command = command_modifier();
+
+        harness.click((5.0, 5.0));
+        harness.key_down(KbKey::Character("a".to_string()), command);
+        harness.key_down(KbKey::Character("x".to_string()), command);
+        assert_eq!(buffer.get_untracked(), "secret");
+        assert_eq!(
+            AppContext::with_clipboard(|clipboard| clipboard.get_string()),
+            None
+        );
+
+        let tree = harness.accessibility_tree();
+        let (_, input) = tree
+            .nodes
+            .iter()
+            .find(|(_, node)| node.role() == Role::TextField)
+            .unwrap();
+        assert_eq!(input.value(), Some("••••••"));
+        assert!(input.is_protected());
+        assert_eq!(input.placeholder(), Some("Password"));
+    }
 }
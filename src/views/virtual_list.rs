@@ -1,5 +1,6 @@
 use std::{hash::Hash, marker::PhantomData, ops::Range};
 
+use accesskit::{NodeBuilder, Role};
 use glazier::kurbo::{Rect, Size};
 use leptos_reactive::{
     create_effect, create_signal, ScopeDisposer, SignalGet, SignalSet, WriteSignal,
@@ -9,7 +10,7 @@ use taffy::{prelude::Node, style::Dimension};
 
 use crate::{
     app_handle::AppContext,
-    context::LayoutCx,
+    context::{AccessibilityCx, LayoutCx},
     id::Id,
     view::{ChangeFlags, View},
 };
@@ -251,7 +252,13 @@ where
             self.after_size = state.after_size;
             AppContext::save();
             AppContext::set_current(self.cx);
-            apply_diff(cx.app_state, state.diff, &mut self.children, &self.view_fn);
+            apply_diff(
+                cx.app_state,
+                state.diff,
+                &mut self.children,
+                &self.view_fn,
+                None,
+            );
             AppContext::restore();
             cx.request_layout(self.id());
             ChangeFlags::LAYOUT
@@ -368,6 +375,10 @@ where
             }
         }
     }
+
+    fn accessibility(&mut self, _cx: &mut AccessibilityCx) -> Option<NodeBuilder> {
+        Some(NodeBuilder::new(Role::List))
+    }
 }
 
 impl<T: Clone> VirtualListVector<T> for im::Vector<T> {
@@ -0,0 +1,761 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use floem_renderer::cosmic_text::{SubpixelBin, SwashCache, SwashContent, SwashImage, TextLayout};
+use floem_renderer::tiny_skia::{
+    self, FillRule, FilterQuality, GradientStop, LineCap, LineJoin, LinearGradient, Mask, Paint,
+    PathBuilder, Pattern, Pixmap, PixmapPaint, RadialGradient, Shader, SpreadMode, Stroke,
+    StrokeDash, Transform,
+};
+use floem_renderer::Renderer;
+use peniko::{
+    kurbo::{Affine, PathEl, Point, Rect, RoundedRectRadii, Shape},
+    BrushRef, Cap, Color, Extend, Gradient, GradientKind, Join,
+};
+
+/// A CPU renderer that rasterizes into an in-memory RGBA [`Pixmap`].
+///
+/// Every z-index gets its own layer, and the layers are composited in order
+/// in [`Renderer::finish`], so the result matches the ordering of the GPU
+/// backends even though tiny-skia paints immediately.
+pub struct TinySkiaRenderer {
+    width: u32,
+    height: u32,
+    scale: f64,
+    transform: Affine,
+    z_index: i32,
+    /// The intersection of the pushed clips, in device pixels
+    clip: Option<Mask>,
+    /// The clip from before each push
+    saved_clips: Vec<Option<Mask>>,
+    layers: BTreeMap<i32, Pixmap>,
+    /// The opacity of each pushed group, with the layers of its parent
+    groups: Vec<(f32, BTreeMap<i32, Pixmap>)>,
+    /// The color the layers are composited over in [`Renderer::finish`]
+    background: Color,
+    pixmap: Pixmap,
+    swash_cache: SwashCache,
+}
+
+impl TinySkiaRenderer {
+    pub fn new(width: u32, height: u32, scale: f64) -> Result<Self> {
+        let pixmap = Pixmap::new(width.max(1), height.max(1))
+            .ok_or_else(|| anyhow::anyhow!("can't create pixmap"))?;
+        Ok(Self {
+            width: pixmap.width(),
+            height: pixmap.height(),
+            scale,
+            transform: Affine::IDENTITY,
+            z_index: 0,
+            clip: None,
+            saved_clips: Vec::new(),
+            layers: BTreeMap::new(),
+            groups: Vec::new(),
+            background: Color::WHITE,
+            pixmap,
+            swash_cache: SwashCache::new(),
+        })
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32, scale: f64) {
+        let width = width.max(1);
+        let height = height.max(1);
+        if width != self.width || height != self.height {
+            if let Some(pixmap) = Pixmap::new(width, height) {
+                self.width = width;
+                self.height = height;
+                self.pixmap = pixmap;
+                self.layers.clear();
+                self.groups.clear();
+                self.clip = None;
+                self.saved_clips.clear();
+            }
+        }
+        self.scale = scale;
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// Sets the color the frame is drawn over, which is white by default
+    pub fn set_background(&mut self, color: Color) {
+        self.background = color;
+    }
+
+    /// The image produced by the last [`Renderer::finish`].
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+}
+
+impl TinySkiaRenderer {
+    fn layer(&mut self) -> &mut Pixmap {
+        let (width, height) = (self.width, self.height);
+        self.layers
+            .entry(self.z_index)
+            .or_insert_with(|| Pixmap::new(width, height).unwrap())
+    }
+
+    /// The transform from local coordinates to device pixels.
+    fn device_transform(&self) -> Transform {
+        skia_transform(Affine::scale(self.scale) * self.transform)
+    }
+
+    /// The paint for `brush` over a shape within `bounds`; the pixmap of an image
+    /// brush, or of a gradient tiny-skia can't draw, is kept in `image`, which the
+    /// paint borrows
+    fn brush_to_paint<'b, 'p>(
+        &self,
+        brush: impl Into<BrushRef<'b>>,
+        bounds: Rect,
+        image: &'p mut Option<Pixmap>,
+    ) -> Option<Paint<'p>> {
+        let shader = match brush.into() {
+            BrushRef::Solid(color) => Shader::SolidColor(skia_color(color)),
+            BrushRef::Gradient(g) => {
+                let stops = g
+                    .stops
+                    .iter()
+                    .map(|stop| GradientStop::new(stop.offset, skia_color(stop.color)))
+                    .collect::<Vec<_>>();
+                let mode = match g.extend {
+                    Extend::Pad => SpreadMode::Pad,
+                    Extend::Repeat => SpreadMode::Repeat,
+                    Extend::Reflect => SpreadMode::Reflect,
+                };
+                let transform = self.device_transform();
+                match g.kind {
+                    GradientKind::Linear { start, end } => LinearGradient::new(
+                        skia_point(start),
+                        skia_point(end),
+                        stops,
+                        mode,
+                        transform,
+                    )?,
+                    // tiny-skia's radial gradients start from a point
+                    GradientKind::Radial {
+                        start_center,
+                        start_radius,
+                        end_center,
+                        end_radius,
+                    } if start_radius == 0.0 => RadialGradient::new(
+                        skia_point(start_center),
+                        skia_point(end_center),
+                        end_radius,
+                        stops,
+                        mode,
+                        transform,
+                    )?,
+                    GradientKind::Radial { .. } | GradientKind::Sweep { .. } => {
+                        self.gradient_pattern(g, bounds, image)?
+                    }
+                }
+            }
+            BrushRef::Image(img) => {
+                // Paints the image at its size, from the origin of the local coordinates
+                let pixmap = floem_renderer::Img {
+                    data: img.data.data(),
+                    width: img.width,
+                    height: img.height,
+                    hash: &[],
+                }
+                .to_pixmap(img.width, img.height)?;
+                let pixmap = image.insert(pixmap);
+                Pattern::new(
+                    pixmap.as_ref(),
+                    SpreadMode::Pad,
+                    FilterQuality::Bilinear,
+                    1.0,
+                    self.device_transform(),
+                )
+            }
+        };
+        Some(Paint {
+            shader,
+            ..Default::default()
+        })
+    }
+
+    /// The gradient rendered into a pixmap over `bounds` at the device scale,
+    /// which is kept in `image`
+    fn gradient_pattern<'p>(
+        &self,
+        gradient: &Gradient,
+        bounds: Rect,
+        image: &'p mut Option<Pixmap>,
+    ) -> Option<Shader<'p>> {
+        if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            return None;
+        }
+        // shapes bigger than the window get a stretched image
+        let size = |length: f64, max: u32| ((length * self.scale).ceil() as u32).clamp(1, max);
+        let width = size(bounds.width(), self.width);
+        let height = size(bounds.height(), self.height);
+        let pixels = floem_renderer::render_gradient(gradient, bounds, width, height);
+        let pixmap = floem_renderer::Img {
+            data: &pixels,
+            width,
+            height,
+            hash: &[],
+        }
+        .to_pixmap(width, height)?;
+        let pixmap = image.insert(pixmap);
+        let transform = self
+            .device_transform()
+            .pre_translate(bounds.x0 as f32, bounds.y0 as f32)
+            .pre_scale(
+                (bounds.width() / width as f64) as f32,
+                (bounds.height() / height as f64) as f32,
+            );
+        Some(Pattern::new(
+            pixmap.as_ref(),
+            SpreadMode::Pad,
+            FilterQuality::Bilinear,
+            1.0,
+            transform,
+        ))
+    }
+
+    /// Draws a pixmap that's rendered at the device scale into `rect`
+    fn draw_device_pixmap(&mut self, pixmap: &Pixmap, rect: Rect) {
+        let transform = self
+            .device_transform()
+            .pre_translate(rect.x0 as f32, rect.y0 as f32)
+            .pre_scale(1.0 / self.scale as f32, 1.0 / self.scale as f32);
+        let clip = self.clip.take();
+        self.layer().draw_pixmap(
+            0,
+            0,
+            pixmap.as_ref(),
+            &PixmapPaint::default(),
+            transform,
+            clip.as_ref(),
+        );
+        self.clip = clip;
+    }
+
+    /// Draws the glyph `image` at `x` and `y` in the pixels of the text, which
+    /// `transform` places in the window
+    fn draw_glyph(
+        &mut self,
+        image: &SwashImage,
+        x: i32,
+        y: i32,
+        color: Color,
+        transform: Transform,
+    ) {
+        let width = image.placement.width;
+        let height = image.placement.height;
+        let mut glyph = match Pixmap::new(width, height) {
+            Some(glyph) => glyph,
+            None => return,
+        };
+        let pixels = glyph.data_mut();
+        match image.content {
+            SwashContent::Mask => {
+                for (pixel, coverage) in pixels.chunks_exact_mut(4).zip(image.data.iter()) {
+                    pixel.copy_from_slice(&premultiply(color, *coverage));
+                }
+            }
+            SwashContent::SubpixelMask => {
+                for (pixel, coverage) in pixels.chunks_exact_mut(4).zip(image.data.chunks_exact(4))
+                {
+                    let coverage =
+                        ((coverage[0] as u16 + coverage[1] as u16 + coverage[2] as u16) / 3) as u8;
+                    pixel.copy_from_slice(&premultiply(color, coverage));
+                }
+            }
+            SwashContent::Color => {
+                for (pixel, rgba) in pixels.chunks_exact_mut(4).zip(image.data.chunks_exact(4)) {
+                    let color = Color::rgba8(rgba[0], rgba[1], rgba[2], 255);
+                    pixel.copy_from_slice(&premultiply(color, rgba[3]));
+                }
+            }
+        }
+
+        let x = x + image.placement.left;
+        let y = y - image.placement.top;
+        let paint = PixmapPaint {
+            quality: if transform.is_identity() {
+                FilterQuality::Nearest
+            } else {
+                FilterQuality::Bilinear
+            },
+            ..Default::default()
+        };
+        let clip = self.clip.take();
+        self.layer()
+            .draw_pixmap(x, y, glyph.as_ref(), &paint, transform, clip.as_ref());
+        self.clip = clip;
+    }
+}
+
+impl Renderer for TinySkiaRenderer {
+    fn begin(&mut self) {
+        self.transform = Affine::IDENTITY;
+        self.z_index = 0;
+        self.clip = None;
+        self.saved_clips.clear();
+        self.layers.clear();
+        self.groups.clear();
+    }
+
+    fn transform(&mut self, transform: Affine) {
+        self.transform = transform;
+    }
+
+    fn set_z_index(&mut self, z_index: i32) {
+        self.z_index = z_index;
+    }
+
+    fn push_layer(&mut self, opacity: f32) {
+        let parent = std::mem::take(&mut self.layers);
+        self.groups.push((opacity.clamp(0.0, 1.0), parent));
+    }
+
+    fn pop_layer(&mut self) {
+        let (opacity, parent) = match self.groups.pop() {
+            Some(group) => group,
+            None => return,
+        };
+        let group = std::mem::replace(&mut self.layers, parent);
+        let paint = PixmapPaint {
+            opacity,
+            ..Default::default()
+        };
+        for (z_index, pixmap) in group {
+            let (width, height) = (self.width, self.height);
+            self.layers
+                .entry(z_index)
+                .or_insert_with(|| Pixmap::new(width, height).unwrap())
+                .draw_pixmap(0, 0, pixmap.as_ref(), &paint, Transform::identity(), None);
+        }
+    }
+
+    fn push_clip(&mut self, shape: &impl Shape) {
+        let mask = skia_path(shape).and_then(|path| {
+            let mut mask = Mask::new(self.width, self.height)?;
+            mask.fill_path(&path, FillRule::Winding, true, self.device_transform());
+            Some(mask)
+        });
+        let mut mask = match mask {
+            Some(mask) => mask,
+            // an empty shape clips everything away
+            None => Mask::new(self.width, self.height).unwrap(),
+        };
+        if let Some(clip) = self.clip.as_ref() {
+            for (coverage, outer) in mask.data_mut().iter_mut().zip(clip.data()) {
+                *coverage = (*coverage as u16 * *outer as u16 / 255) as u8;
+            }
+        }
+        self.saved_clips.push(self.clip.replace(mask));
+    }
+
+    fn push_unclipped(&mut self) {
+        self.saved_clips.push(self.clip.take());
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip = self.saved_clips.pop().flatten();
+    }
+
+    fn stroke<'b>(
+        &mut self,
+        shape: &impl Shape,
+        brush: impl Into<BrushRef<'b>>,
+        stroke: &peniko::Stroke,
+    ) {
+        let mut image = None;
+        let bounds = shape
+            .bounding_box()
+            .inflate(stroke.width as f64, stroke.width as f64);
+        let paint = match self.brush_to_paint(brush, bounds, &mut image) {
+            Some(paint) => paint,
+            None => return,
+        };
+        let path = match skia_path(shape) {
+            Some(path) => path,
+            None => return,
+        };
+        let stroke = skia_stroke(stroke);
+        let transform = self.device_transform();
+        let clip = self.clip.take();
+        self.layer()
+            .stroke_path(&path, &paint, &stroke, transform, clip.as_ref());
+        self.clip = clip;
+    }
+
+    fn fill<'b>(&mut self, path: &impl Shape, brush: impl Into<BrushRef<'b>>) {
+        let mut image = None;
+        let paint = match self.brush_to_paint(brush, path.bounding_box(), &mut image) {
+            Some(paint) => paint,
+            None => return,
+        };
+        let path = match skia_path(path) {
+            Some(path) => path,
+            None => return,
+        };
+        let transform = self.device_transform();
+        let clip = self.clip.take();
+        self.layer()
+            .fill_path(&path, &paint, FillRule::Winding, transform, clip.as_ref());
+        self.clip = clip;
+    }
+
+    fn draw_text(&mut self, layout: &TextLayout, pos: impl Into<Point>) {
+        let pos: Point = pos.into();
+        let [a, b, c, d, e, f] = self.transform.as_coeffs();
+        // glyphs are rasterized at the window's scale, so a translation is
+        // applied by placing them at whole pixels, anything else by
+        // transforming their pixels into the window
+        let (offset, transform) = if (a, b, c, d) == (1.0, 0.0, 0.0, 1.0) {
+            ((e, f), Transform::identity())
+        } else {
+            let transform =
+                Affine::scale(self.scale) * self.transform * Affine::scale(1.0 / self.scale);
+            ((0.0, 0.0), skia_transform(transform))
+        };
+        let scale = self.scale as f32;
+        for line in layout.layout_runs() {
+            for glyph_run in line.glyphs {
+                let x = glyph_run.x + (pos.x + offset.0) as f32;
+                let y = line.line_y + (pos.y + offset.1) as f32;
+
+                let (glyph_x, subpx_x) = SubpixelBin::new(x * scale);
+                let (glyph_y, subpx_y) = SubpixelBin::new((y * scale).round());
+
+                let mut cache_key = glyph_run.cache_key;
+                cache_key.font_size = (glyph_run.font_size * scale).round() as u32;
+                cache_key.x_bin = subpx_x;
+                cache_key.y_bin = subpx_y;
+                if let Some(image) = self.swash_cache.get_image_uncached(cache_key) {
+                    self.draw_glyph(&image, glyph_x, glyph_y, glyph_run.color, transform);
+                }
+            }
+        }
+    }
+
+    fn draw_svg<'b>(
+        &mut self,
+        svg: floem_renderer::Svg<'b>,
+        rect: Rect,
+        brush: Option<impl Into<BrushRef<'b>>>,
+    ) {
+        let width = (rect.width() * self.scale).round() as u32;
+        let height = (rect.height() * self.scale).round() as u32;
+        let mut img = match Pixmap::new(width.max(1), height.max(1)) {
+            Some(img) => img,
+            None => return,
+        };
+        let rtree = resvg::Tree::from_usvg(svg.tree);
+        let svg_scale = (img.width() as f64 / rtree.size.width())
+            .min(img.height() as f64 / rtree.size.height()) as f32;
+        rtree.render(
+            tiny_skia::Transform::from_scale(svg_scale, svg_scale),
+            &mut img.as_mut(),
+        );
+
+        if let Some(BrushRef::Solid(color)) = brush.map(|brush| brush.into()) {
+            // Tint the svg with the brush color, keeping its coverage
+            for pixel in img.data_mut().chunks_exact_mut(4) {
+                pixel.copy_from_slice(&premultiply(color, pixel[3]));
+            }
+        }
+
+        self.draw_device_pixmap(&img, rect);
+    }
+
+    fn draw_img(&mut self, img: floem_renderer::Img<'_>, rect: Rect) {
+        let width = (rect.width() * self.scale).round() as u32;
+        let height = (rect.height() * self.scale).round() as u32;
+        if let Some(pixmap) = img.to_pixmap(width.max(1), height.max(1)) {
+            self.draw_device_pixmap(&pixmap, rect);
+        }
+    }
+
+    fn draw_box_shadow(
+        &mut self,
+        rect: Rect,
+        radii: RoundedRectRadii,
+        color: Color,
+        blur_radius: f64,
+    ) {
+        let extent = floem_renderer::shadow_extent(blur_radius * self.scale) / self.scale;
+        let pixmap = floem_renderer::box_shadow_pixmap(
+            rect.width() * self.scale,
+            rect.height() * self.scale,
+            scale_radii(radii, self.scale),
+            color,
+            blur_radius * self.scale,
+        );
+        if let Some(pixmap) = pixmap {
+            self.draw_device_pixmap(&pixmap, rect.inflate(extent, extent));
+        }
+    }
+
+    fn finish(&mut self) {
+        self.pixmap.fill(skia_color(self.background));
+        for layer in self.layers.values() {
+            self.pixmap.draw_pixmap(
+                0,
+                0,
+                layer.as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+    }
+}
+
+fn skia_path(shape: &impl Shape) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    for el in shape.path_elements(0.1) {
+        match el {
+            PathEl::MoveTo(p) => builder.move_to(p.x as f32, p.y as f32),
+            PathEl::LineTo(p) => builder.line_to(p.x as f32, p.y as f32),
+            PathEl::QuadTo(p1, p2) => {
+                builder.quad_to(p1.x as f32, p1.y as f32, p2.x as f32, p2.y as f32)
+            }
+            PathEl::CurveTo(p1, p2, p3) => builder.cubic_to(
+                p1.x as f32,
+                p1.y as f32,
+                p2.x as f32,
+                p2.y as f32,
+                p3.x as f32,
+                p3.y as f32,
+            ),
+            PathEl::ClosePath => builder.close(),
+        }
+    }
+    builder.finish()
+}
+
+fn scale_radii(radii: RoundedRectRadii, scale: f64) -> RoundedRectRadii {
+    RoundedRectRadii::new(
+        radii.top_left * scale,
+        radii.top_right * scale,
+        radii.bottom_right * scale,
+        radii.bottom_left * scale,
+    )
+}
+
+fn skia_transform(affine: Affine) -> Transform {
+    let [a, b, c, d, e, f] = affine.as_coeffs();
+    Transform::from_row(a as f32, b as f32, c as f32, d as f32, e as f32, f as f32)
+}
+
+fn skia_stroke(stroke: &peniko::Stroke) -> Stroke {
+    let cap = |cap| match cap {
+        Cap::Butt => LineCap::Butt,
+        Cap::Square => LineCap::Square,
+        Cap::Round => LineCap::Round,
+    };
+    let mut dashes: Vec<f32> = stroke.dash_pattern.to_vec();
+    // tiny-skia wants pairs of dashes and gaps, an odd pattern repeats to make them
+    if dashes.len() % 2 == 1 {
+        dashes.extend_from_within(..);
+    }
+    Stroke {
+        width: stroke.width,
+        miter_limit: stroke.miter_limit,
+        // tiny-skia has one cap for both ends
+        line_cap: cap(stroke.start_cap),
+        line_join: match stroke.join {
+            Join::Bevel => LineJoin::Bevel,
+            Join::Miter => LineJoin::Miter,
+            Join::Round => LineJoin::Round,
+        },
+        dash: StrokeDash::new(dashes, stroke.dash_offset),
+    }
+}
+
+fn skia_point(point: Point) -> tiny_skia::Point {
+    tiny_skia::Point::from_xy(point.x as f32, point.y as f32)
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(color.r, color.g, color.b, color.a)
+}
+
+/// Premultiplied RGBA bytes for `color` at the given coverage.
+fn premultiply(color: Color, coverage: u8) -> [u8; 4] {
+    let a = (color.a as u16 * coverage as u16 / 255) as u8;
+    let mul = |c: u8| (c as u16 * a as u16 / 255) as u8;
+    [mul(color.r), mul(color.g), mul(color.b), a]
+}
+
+#[cfg(test)]
+mod tests {
+    use floem_renderer::Renderer;
+    use peniko::{
+        kurbo::{Affine, Line, Point, Rect},
+        Cap, Color, ColorStop, Extend, Gradient, GradientKind, Stroke,
+    };
+
+    use super::TinySkiaRenderer;
+
+    fn pixel(renderer: &TinySkiaRenderer, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let pixel = renderer.pixmap().pixel(x, y).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha())
+    }
+
+    #[test]
+    fn fill_with_transform_and_scale() {
+        let mut renderer = TinySkiaRenderer::new(40, 40, 2.0).unwrap();
+        renderer.begin();
+        renderer.transform(Affine::translate((5.0, 5.0)));
+        renderer.fill(&Rect::new(0.0, 0.0, 5.0, 5.0), Color::RED);
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 5, 5), (255, 255, 255, 255));
+        assert_eq!(pixel(&renderer, 15, 15), (255, 0, 0, 255));
+        assert_eq!(pixel(&renderer, 25, 25), (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn clip_limits_fill() {
+        let mut renderer = TinySkiaRenderer::new(20, 20, 1.0).unwrap();
+        renderer.begin();
+        renderer.push_clip(&Rect::new(0.0, 0.0, 10.0, 20.0));
+        renderer.fill(&Rect::new(0.0, 0.0, 20.0, 20.0), Color::BLUE);
+        renderer.pop_clip();
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 5, 10), (0, 0, 255, 255));
+        assert_eq!(pixel(&renderer, 15, 10), (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn transparent_background_keeps_undrawn_pixels() {
+        let mut renderer = TinySkiaRenderer::new(20, 20, 1.0).unwrap();
+        renderer.set_background(Color::TRANSPARENT);
+        renderer.begin();
+        renderer.fill(&Rect::new(0.0, 0.0, 10.0, 20.0), Color::BLUE);
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 5, 10), (0, 0, 255, 255));
+        assert_eq!(pixel(&renderer, 15, 10), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn nested_clips_intersect() {
+        let mut renderer = TinySkiaRenderer::new(40, 40, 1.0).unwrap();
+        renderer.begin();
+        renderer.push_clip(&Rect::new(0.0, 0.0, 40.0, 40.0).to_rounded_rect(20.0));
+        renderer.push_clip(&Rect::new(0.0, 0.0, 20.0, 40.0));
+        renderer.fill(&Rect::new(0.0, 0.0, 40.0, 40.0), Color::BLUE);
+        renderer.pop_clip();
+        renderer.fill(&Rect::new(30.0, 15.0, 40.0, 25.0), Color::RED);
+        renderer.pop_clip();
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 10, 20), (0, 0, 255, 255));
+        // outside of the rounded corner, and outside of the inner clip
+        assert_eq!(pixel(&renderer, 1, 1), (255, 255, 255, 255));
+        assert_eq!(pixel(&renderer, 25, 20), (255, 255, 255, 255));
+        // back to the outer clip only
+        assert_eq!(pixel(&renderer, 35, 20), (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn dashed_stroke_leaves_gaps() {
+        let mut renderer = TinySkiaRenderer::new(20, 10, 1.0).unwrap();
+        renderer.begin();
+        let stroke = Stroke::new(2.0)
+            .with_caps(Cap::Butt)
+            .with_dashes(0.0, [4.0, 4.0]);
+        renderer.stroke(&Line::new((0.0, 5.0), (20.0, 5.0)), Color::RED, &stroke);
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 2, 5), (255, 0, 0, 255));
+        assert_eq!(pixel(&renderer, 6, 5), (255, 255, 255, 255));
+        assert_eq!(pixel(&renderer, 10, 5), (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn higher_z_index_paints_on_top() {
+        let mut renderer = TinySkiaRenderer::new(10, 10, 1.0).unwrap();
+        renderer.begin();
+        renderer.set_z_index(1);
+        renderer.fill(&Rect::new(0.0, 0.0, 10.0, 10.0), Color::RED);
+        renderer.set_z_index(0);
+        renderer.fill(&Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLUE);
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 5, 5), (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn sweep_gradient_goes_around_the_center() {
+        let mut renderer = TinySkiaRenderer::new(40, 40, 1.0).unwrap();
+        renderer.begin();
+        let gradient = Gradient {
+            kind: GradientKind::Sweep {
+                center: Point::new(20.0, 20.0),
+                start_angle: 0.0,
+                end_angle: std::f32::consts::TAU,
+            },
+            extend: Extend::Pad,
+            stops: [
+                ColorStop {
+                    offset: 0.0,
+                    color: Color::RED,
+                },
+                ColorStop {
+                    offset: 1.0,
+                    color: Color::BLUE,
+                },
+            ]
+            .into_iter()
+            .collect(),
+        };
+        renderer.fill(&Rect::new(0.0, 0.0, 40.0, 40.0), &gradient);
+        renderer.finish();
+
+        // a quarter of the way around, then half of it
+        let (r, _, b, _) = pixel(&renderer, 20, 35);
+        assert!(r > 150 && b < 100, "{r} {b}");
+        let (r, _, b, _) = pixel(&renderer, 5, 20);
+        assert!(
+            (100..156).contains(&r) && (100..156).contains(&b),
+            "{r} {b}"
+        );
+    }
+
+    #[test]
+    fn box_shadow_fades_around_the_rect() {
+        let mut renderer = TinySkiaRenderer::new(40, 40, 1.0).unwrap();
+        renderer.begin();
+        renderer.draw_box_shadow(
+            Rect::new(10.0, 10.0, 30.0, 30.0),
+            0.0.into(),
+            Color::BLACK,
+            4.0,
+        );
+        renderer.finish();
+
+        assert_eq!(pixel(&renderer, 20, 20), (0, 0, 0, 255));
+        let (edge, ..) = pixel(&renderer, 10, 20);
+        assert!(edge > 0 && edge < 255);
+        assert_eq!(pixel(&renderer, 2, 20), (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn layer_fades_the_group_as_a_whole() {
+        let mut renderer = TinySkiaRenderer::new(20, 10, 1.0).unwrap();
+        renderer.begin();
+        renderer.push_layer(0.5);
+        renderer.fill(&Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        // overlapping shapes in a group don't show through each other
+        renderer.fill(&Rect::new(0.0, 0.0, 10.0, 10.0), Color::BLACK);
+        renderer.pop_layer();
+        renderer.fill(&Rect::new(10.0, 0.0, 20.0, 10.0), Color::BLACK);
+        renderer.finish();
+
+        let (r, g, b, _) = pixel(&renderer, 5, 5);
+        assert!((127..=128).contains(&r) && r == g && g == b);
+        assert_eq!(pixel(&renderer, 15, 5), (0, 0, 0, 255));
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use peniko::{
+    kurbo::{Point, Rect},
+    Extend, Gradient, GradientKind,
+};
+
+/// The largest side of the images gradients are rendered into. Gradients are
+/// smooth, so bigger shapes get their image stretched.
+pub(crate) const MAX_GRADIENT_IMAGE_SIZE: u32 = 256;
+
+/// Whether vger's linear gradient shader can draw the gradient, which only
+/// blends two colors between the start and the end
+pub(crate) fn is_simple_linear(gradient: &Gradient) -> bool {
+    matches!(gradient.kind, GradientKind::Linear { .. })
+        && matches!(gradient.extend, Extend::Pad)
+        && gradient.stops.len() == 2
+        && gradient.stops[0].offset == 0.0
+        && gradient.stops[1].offset == 1.0
+}
+
+/// Identifies the image of the gradient over `bounds`, which doesn't change
+/// when the gradient moves along with the bounds
+pub(crate) fn gradient_key(gradient: &Gradient, bounds: Rect, width: u32, height: u32) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let origin = bounds.origin().to_vec2();
+    let mut hash_point = |point: Point| {
+        let point = point - origin;
+        point.x.to_bits().hash(&mut hasher);
+        point.y.to_bits().hash(&mut hasher);
+    };
+    match gradient.kind {
+        GradientKind::Linear { start, end } => {
+            hash_point(start);
+            hash_point(end);
+        }
+        GradientKind::Radial {
+            start_center,
+            start_radius,
+            end_center,
+            end_radius,
+        } => {
+            hash_point(start_center);
+            hash_point(end_center);
+            start_radius.to_bits().hash(&mut hasher);
+            end_radius.to_bits().hash(&mut hasher);
+        }
+        GradientKind::Sweep {
+            center,
+            start_angle,
+            end_angle,
+        } => {
+            hash_point(center);
+            start_angle.to_bits().hash(&mut hasher);
+            end_angle.to_bits().hash(&mut hasher);
+        }
+    }
+    std::mem::discriminant(&gradient.kind).hash(&mut hasher);
+    std::mem::discriminant(&gradient.extend).hash(&mut hasher);
+    for stop in gradient.stops.iter() {
+        stop.offset.to_bits().hash(&mut hasher);
+        (stop.color.r, stop.color.g, stop.color.b, stop.color.a).hash(&mut hasher);
+    }
+    bounds.width().to_bits().hash(&mut hasher);
+    bounds.height().to_bits().hash(&mut hasher);
+    (width, height).hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+/// Images uploaded to vger, like the images of gradients and image brushes,
+/// which are deleted once a frame goes by without them being drawn, so a
+/// gradient or image that changes every frame doesn't leave a texture behind
+/// every frame.
+pub(crate) struct ImageCache<T> {
+    /// The images, and whether they were drawn since the last eviction
+    images: HashMap<u64, (T, bool)>,
+}
+
+impl<T: Copy> ImageCache<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            images: HashMap::new(),
+        }
+    }
+
+    /// The image for `key`, which is made with `create` if it isn't cached
+    pub(crate) fn get_or_insert_with(&mut self, key: u64, create: impl FnOnce() -> T) -> T {
+        let (image, used) = self.images.entry(key).or_insert_with(|| (create(), false));
+        *used = true;
+        *image
+    }
+
+    /// Deletes the images that weren't drawn since the last eviction, which
+    /// happens at the start of every frame
+    pub(crate) fn evict(&mut self, mut delete: impl FnMut(T)) {
+        self.images.retain(|_, (image, used)| {
+            if !*used {
+                delete(*image);
+            }
+            std::mem::replace(used, false)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageCache;
+
+    #[test]
+    fn evicts_images_not_drawn_in_the_last_frame() {
+        let mut cache = ImageCache::new();
+        let mut next = 0;
+        let mut create = || {
+            next += 1;
+            next
+        };
+        assert_eq!(cache.get_or_insert_with(10, &mut create), 1);
+        assert_eq!(cache.get_or_insert_with(20, &mut create), 2);
+        assert_eq!(cache.get_or_insert_with(10, &mut create), 1);
+
+        let mut deleted = Vec::new();
+        cache.evict(|image| deleted.push(image));
+        assert!(deleted.is_empty());
+
+        // only the first image is drawn in the next frame
+        assert_eq!(cache.get_or_insert_with(10, &mut create), 1);
+        cache.evict(|image| deleted.push(image));
+        assert_eq!(deleted, vec![2]);
+
+        cache.evict(|image| deleted.push(image));
+        assert_eq!(deleted, vec![2, 1]);
+        assert_eq!(cache.get_or_insert_with(20, &mut create), 3);
+    }
+}
@@ -1,11 +1,23 @@
+mod gradient;
+mod image_cache;
+mod offscreen;
+mod stroke;
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
 use anyhow::Result;
 use floem_renderer::cosmic_text::{SubpixelBin, SwashCache, SwashImage, TextLayout};
 use floem_renderer::{tiny_skia, Renderer};
+use image_cache::ImageCache;
+use offscreen::{Offscreen, OffscreenKind};
 use peniko::{
-    kurbo::{Affine, Point, Rect, Shape, Vec2},
-    BrushRef, Color, GradientKind,
+    kurbo::{Affine, CubicBez, PathEl, PathSeg, Point, Rect, RoundedRectRadii, Shape, Vec2},
+    BrushRef, Cap, Color, Gradient, GradientKind, Image, Join, Stroke,
 };
-use vger::{PaintIndex, Vger};
+use vger::{ImageIndex, PaintIndex, Vger};
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration, TextureFormat};
 
 pub struct VgerRenderer {
@@ -16,7 +28,26 @@ pub struct VgerRenderer {
     config: SurfaceConfiguration,
     scale: f64,
     transform: Affine,
+    /// The translation of the current transform, which is added to the points
+    /// given to vger. Rotation and scale go through vger's own transform.
+    offset: Vec2,
+    /// Whether vger's transform is saved and set for a rotation or scale
+    has_vger_transform: bool,
+    /// The intersection of the bounding boxes of the pushed clips, in window
+    /// coordinates, which vger's scissor is set to
     clip: Option<Rect>,
+    /// The clip from before each push
+    saved_clips: Vec<Option<Rect>>,
+    /// Where everything is drawn while a clip that isn't a rect, or a faded
+    /// layer, is pushed, since vger's scissor is only a rect and vger draws
+    /// straight to the window
+    offscreen: Option<Offscreen>,
+    /// The images of the offscreen layers
+    offscreen_images: ImageCache<ImageIndex>,
+    /// The images of the gradients vger can't draw with its shaders
+    gradient_images: ImageCache<ImageIndex>,
+    /// The images of image brushes, by the id of their data
+    brush_images: ImageCache<ImageIndex>,
 }
 
 impl VgerRenderer {
@@ -77,7 +108,14 @@ impl VgerRenderer {
             scale,
             config,
             transform: Affine::IDENTITY,
+            offset: Vec2::ZERO,
+            has_vger_transform: false,
             clip: None,
+            saved_clips: Vec::new(),
+            offscreen: None,
+            offscreen_images: ImageCache::new(),
+            gradient_images: ImageCache::new(),
+            brush_images: ImageCache::new(),
         })
     }
 
@@ -96,38 +134,208 @@ impl VgerRenderer {
 }
 
 impl VgerRenderer {
-    fn brush_to_paint<'b>(&mut self, brush: impl Into<BrushRef<'b>>) -> Option<PaintIndex> {
+    /// The paint of the brush for a shape within `bounds`
+    fn brush_to_paint<'b>(
+        &mut self,
+        brush: impl Into<BrushRef<'b>>,
+        bounds: Rect,
+    ) -> Option<PaintIndex> {
         let paint = match brush.into() {
             BrushRef::Solid(color) => self.vger.color_paint(vger_color(color)),
             BrushRef::Gradient(g) => match g.kind {
-                GradientKind::Linear { start, end } => {
-                    let mut stops = g.stops.iter();
-                    let inner_color = stops.next()?;
-                    let outer_color = stops.next()?;
-                    let inner_color = vger_color(inner_color.color);
-                    let outer_color = vger_color(outer_color.color);
-                    let start = vger::defs::LocalPoint::new(start.x as f32, start.y as f32);
-                    let end = vger::defs::LocalPoint::new(end.x as f32, end.y as f32);
+                GradientKind::Linear { start, end } if gradient::is_simple_linear(g) => {
+                    let inner_color = vger_color(g.stops[0].color);
+                    let outer_color = vger_color(g.stops[1].color);
+                    let start = self.vger_point(start);
+                    let end = self.vger_point(end);
                     self.vger
                         .linear_gradient(start, end, inner_color, outer_color, 0.0)
                 }
-                GradientKind::Radial { .. } => return None,
-                GradientKind::Sweep { .. } => return None,
+                _ => self.gradient_image_paint(g, bounds)?,
             },
-            BrushRef::Image(_) => return None,
+            BrushRef::Image(image) => self.image_paint(image)?,
         };
         Some(paint)
     }
 
+    /// Paints the image at its size, from the origin of the local coordinates
+    fn image_paint(&mut self, image: &Image) -> Option<PaintIndex> {
+        if image.width == 0 || image.height == 0 {
+            return None;
+        }
+        let vger = &mut self.vger;
+        let index = self.brush_images.get_or_insert_with(image.data.id(), || {
+            vger.create_image_pixels(image.data.data(), image.width, image.height)
+        });
+        let start = self.vger_point(Point::ZERO);
+        let end = self.vger_point(Point::new(image.width as f64, image.height as f64));
+        Some(self.vger.image_pattern(start, end, index, 1.0))
+    }
+
+    /// Paints the gradient with an image of it over `bounds`, for gradients vger
+    /// has no shader for, like radial and sweep gradients or ones with more stops
+    fn gradient_image_paint(&mut self, gradient: &Gradient, bounds: Rect) -> Option<PaintIndex> {
+        if gradient.stops.is_empty() || bounds.area() <= 0.0 {
+            return None;
+        }
+        let start = self.vger_point(bounds.origin());
+        let end = self.vger_point(Point::new(bounds.x1, bounds.y1));
+        let image_size =
+            |size: f32| (size.abs().ceil() as u32).clamp(1, gradient::MAX_GRADIENT_IMAGE_SIZE);
+        let width = image_size(end.x - start.x);
+        let height = image_size(end.y - start.y);
+
+        let key = gradient::gradient_key(gradient, bounds, width, height);
+        let vger = &mut self.vger;
+        let image = self.gradient_images.get_or_insert_with(key, || {
+            let pixels = floem_renderer::render_gradient(gradient, bounds, width, height);
+            vger.create_image_pixels(&pixels, width, height)
+        });
+        Some(self.vger.image_pattern(start, end, image, 1.0))
+    }
+
+    /// How far flattened curves may be off, in local coordinates, so they're
+    /// within a tenth of a device pixel
+    fn tolerance(&self) -> f64 {
+        0.1 / self.scale
+    }
+
+    /// Fills any shape with vger's path fill, which is made of quadratic curves.
+    /// Lines become curves with their control point in the middle, and cubic
+    /// curves are approximated with quadratic ones.
+    fn fill_path(&mut self, path: &impl Shape, paint: PaintIndex) {
+        let tolerance = self.tolerance();
+        let mut start = Point::ZERO;
+        let mut current = Point::ZERO;
+        for element in path.path_elements(tolerance) {
+            match element {
+                PathEl::MoveTo(p) => {
+                    self.vger.move_to(self.vger_point(p));
+                    start = p;
+                    current = p;
+                }
+                PathEl::LineTo(p) => {
+                    self.line_to(current, p);
+                    current = p;
+                }
+                PathEl::QuadTo(p1, p2) => {
+                    self.vger.quad_to(self.vger_point(p1), self.vger_point(p2));
+                    current = p2;
+                }
+                PathEl::CurveTo(p1, p2, p3) => {
+                    let cubic = CubicBez::new(current, p1, p2, p3);
+                    for (_, _, quad) in cubic.to_quads(tolerance) {
+                        self.vger
+                            .quad_to(self.vger_point(quad.p1), self.vger_point(quad.p2));
+                    }
+                    current = p3;
+                }
+                PathEl::ClosePath => {
+                    if current != start {
+                        self.line_to(current, start);
+                    }
+                    current = start;
+                }
+            }
+        }
+        self.vger.fill(paint);
+    }
+
+    fn line_to(&mut self, from: Point, to: Point) {
+        self.vger
+            .quad_to(self.vger_point(from.midpoint(to)), self.vger_point(to));
+    }
+
     fn vger_point(&self, point: Point) -> vger::defs::LocalPoint {
-        let coeffs = self.transform.as_coeffs();
-        let point = point + Vec2::new(coeffs[4], coeffs[5]);
+        let point = point + self.offset;
         vger::defs::LocalPoint::new(
             (point.x * self.scale).round() as f32,
             (point.y * self.scale).round() as f32,
         )
     }
 
+    /// Sets vger's scissor to the current clip, which is in window coordinates
+    fn apply_clip(&mut self) {
+        let clip = match self.clip {
+            Some(clip) => clip,
+            None => {
+                self.vger.reset_scissor();
+                return;
+            }
+        };
+        // the scissor is in vger's local coordinates, so it's set without the
+        // rotation and scale
+        let transform = self.transform;
+        self.transform(Affine::IDENTITY);
+        self.vger.scissor(self.vger_rect(clip));
+        self.transform(transform);
+    }
+
+    /// Starts an offscreen layer if `shape` under the current transform isn't a
+    /// rect vger's scissor can clip to, for the part of the window within the clip
+    fn push_clip_offscreen(&mut self, shape: &impl Shape, rect: Rect) {
+        let [_, b, c, _, _, _] = self.transform.as_coeffs();
+        let is_rect = shape.as_rect().is_some()
+            || shape
+                .as_rounded_rect()
+                .map_or(false, |rect| rect.radii().as_single_radius() == Some(0.0));
+        if is_rect && b == 0.0 && c == 0.0 {
+            return;
+        }
+        if let Some(rect) = self.device_rect(rect) {
+            self.offscreen = Offscreen::clip(rect, self.scale, self.transform, shape);
+        }
+    }
+
+    /// The part of the window within `rect`, in device pixels, or none if
+    /// there's nothing of it to draw
+    fn device_rect(&self, rect: Rect) -> Option<Rect> {
+        let window = Rect::new(
+            0.0,
+            0.0,
+            self.config.width as f64,
+            self.config.height as f64,
+        );
+        let rect = Affine::scale(self.scale)
+            .transform_rect_bbox(rect)
+            .expand()
+            .intersect(window);
+        (rect.width() >= 1.0 && rect.height() >= 1.0).then_some(rect)
+    }
+
+    /// Draws the image of an offscreen layer where it was taken from the window
+    fn draw_offscreen(&mut self, layer: Offscreen) {
+        let rect = layer.rect;
+        let z_index = layer.z_index;
+        let opacity = layer.opacity;
+        if let Some(pixels) = layer.finish() {
+            let width = rect.width() as u32;
+            let height = rect.height() as u32;
+            let mut hasher = DefaultHasher::new();
+            (width, height).hash(&mut hasher);
+            pixels.hash(&mut hasher);
+            let vger = &mut self.vger;
+            let image = self
+                .offscreen_images
+                .get_or_insert_with(hasher.finish(), || {
+                    vger.create_image_pixels(&pixels, width, height)
+                });
+
+            // the rect is in device pixels, so it's drawn without the transform
+            let transform = self.transform;
+            self.transform(Affine::IDENTITY);
+            let rect = Affine::scale(1.0 / self.scale).transform_rect_bbox(rect);
+            let start = self.vger_point(rect.origin());
+            let end = self.vger_point(Point::new(rect.x1, rect.y1));
+            let paint = self.vger.image_pattern(start, end, image, opacity);
+            self.vger.fill_rect(self.vger_rect(rect), 0.0, paint);
+            self.transform(transform);
+        }
+        if let Some(z_index) = z_index {
+            self.vger.set_z_index(z_index);
+        }
+    }
+
     fn vger_rect(&self, rect: Rect) -> vger::defs::LocalRect {
         let origin = rect.origin();
         let origin = self.vger_point(origin);
@@ -143,6 +351,16 @@ impl VgerRenderer {
 impl Renderer for VgerRenderer {
     fn begin(&mut self) {
         self.transform = Affine::IDENTITY;
+        self.offset = Vec2::ZERO;
+        self.has_vger_transform = false;
+        self.clip = None;
+        self.saved_clips.clear();
+        self.offscreen = None;
+        let vger = &mut self.vger;
+        self.gradient_images.evict(|image| vger.delete_image(image));
+        self.brush_images.evict(|image| vger.delete_image(image));
+        self.offscreen_images
+            .evict(|image| vger.delete_image(image));
         self.vger.begin(
             self.config.width as f32,
             self.config.height as f32,
@@ -150,8 +368,42 @@ impl Renderer for VgerRenderer {
         );
     }
 
-    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, width: f64) {
-        let paint = match self.brush_to_paint(brush) {
+    fn stroke<'b>(&mut self, shape: &impl Shape, brush: impl Into<BrushRef<'b>>, stroke: &Stroke) {
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.stroke(shape, brush, stroke);
+            return;
+        }
+        // vger strokes solid rects with sharp corners, and everything else with
+        // round ends and corners, other strokes are outlined and filled
+        let round_ends = stroke.start_cap == Cap::Round && stroke.end_cap == Cap::Round;
+        let native = stroke.dash_pattern.is_empty()
+            && if shape.as_rect().is_some() {
+                stroke.join == Join::Miter
+            } else if shape
+                .as_rounded_rect()
+                .and_then(|rect| rect.radii().as_single_radius())
+                .is_some()
+            {
+                true
+            } else if shape.as_line().is_some() {
+                round_ends
+            } else {
+                round_ends && stroke.join == Join::Round
+            };
+        if !native {
+            let tolerance = self.tolerance();
+            let path = if stroke.dash_pattern.is_empty() {
+                shape.to_path(tolerance)
+            } else {
+                stroke::dash(shape.path_elements(tolerance), stroke)
+            };
+            self.fill(&stroke::outline(&path, stroke, tolerance), brush);
+            return;
+        }
+
+        let width = stroke.width as f64;
+        let bounds = shape.bounding_box().inflate(width / 2.0, width / 2.0);
+        let paint = match self.brush_to_paint(brush, bounds) {
             Some(paint) => paint,
             None => return,
         };
@@ -166,10 +418,14 @@ impl Renderer for VgerRenderer {
                 width,
                 paint,
             );
-        } else if let Some(rect) = shape.as_rounded_rect() {
+        } else if let Some((rect, radius)) = shape
+            .as_rounded_rect()
+            .and_then(|rect| Some((rect.rect(), rect.radii().as_single_radius()?)))
+        {
             let min = rect.origin();
-            let max = min + rect.rect().size().to_vec2();
-            let radius = rect.radii().top_left as f32;
+            let max = min + rect.size().to_vec2();
+            // like fills, other rounded rects are stroked as paths
+            let radius = (radius * self.scale) as f32;
             self.vger.stroke_rect(
                 self.vger_point(min),
                 self.vger_point(max),
@@ -185,10 +441,17 @@ impl Renderer for VgerRenderer {
                 paint,
             );
         } else {
-            for segment in shape.path_segments(0.0) {
+            for segment in shape.path_segments(self.tolerance()) {
                 match segment {
-                    peniko::kurbo::PathSeg::Line(_) => todo!(),
-                    peniko::kurbo::PathSeg::Quad(bez) => {
+                    PathSeg::Line(line) => {
+                        self.vger.stroke_segment(
+                            self.vger_point(line.p0),
+                            self.vger_point(line.p1),
+                            width,
+                            paint,
+                        );
+                    }
+                    PathSeg::Quad(bez) => {
                         self.vger.stroke_bezier(
                             self.vger_point(bez.p0),
                             self.vger_point(bez.p1),
@@ -197,40 +460,65 @@ impl Renderer for VgerRenderer {
                             paint,
                         );
                     }
-                    peniko::kurbo::PathSeg::Cubic(_) => todo!(),
+                    PathSeg::Cubic(bez) => {
+                        for (_, _, quad) in bez.to_quads(self.tolerance()) {
+                            self.vger.stroke_bezier(
+                                self.vger_point(quad.p0),
+                                self.vger_point(quad.p1),
+                                self.vger_point(quad.p2),
+                                width,
+                                paint,
+                            );
+                        }
+                    }
                 }
             }
         }
     }
 
     fn fill<'b>(&mut self, path: &impl Shape, brush: impl Into<BrushRef<'b>>) {
-        let paint = match self.brush_to_paint(brush) {
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.fill(path, brush);
+            return;
+        }
+        let paint = match self.brush_to_paint(brush, path.bounding_box()) {
             Some(paint) => paint,
             None => return,
         };
         if let Some(rect) = path.as_rect() {
             self.vger.fill_rect(self.vger_rect(rect), 0.0, paint);
-        } else if let Some(rect) = path.as_rounded_rect() {
-            self.vger.fill_rect(
-                self.vger_rect(rect.rect()),
-                rect.radii().top_left as f32,
-                paint,
-            );
+        } else if let Some((rect, radius)) = path
+            .as_rounded_rect()
+            .and_then(|rect| Some((rect.rect(), rect.radii().as_single_radius()?)))
+        {
+            // vger's rects have a single radius, other rounded rects are filled as paths
+            let radius = (radius * self.scale) as f32;
+            self.vger.fill_rect(self.vger_rect(rect), radius, paint);
         } else if let Some(circle) = path.as_circle() {
             self.vger.fill_circle(
                 self.vger_point(circle.center),
                 (circle.radius * self.scale) as f32,
                 paint,
             )
+        } else {
+            self.fill_path(path, paint);
         }
     }
 
     fn draw_text(&mut self, layout: &TextLayout, pos: impl Into<Point>) {
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.draw_text(layout, pos);
+            return;
+        }
         let mut swash_cache = SwashCache::new();
-        let transform = self.transform.as_coeffs();
-        let offset = Vec2::new(transform[4], transform[5]);
+        let offset = self.offset;
         let pos: Point = pos.into();
-        let clip = self.clip;
+        // the glyphs are only culled when their points are in window coordinates
+        let clip = if self.has_vger_transform {
+            None
+        } else {
+            self.clip
+        };
         for line in layout.layout_runs() {
             if let Some(rect) = clip {
                 let y = pos.y + offset.y + line.line_y as f64;
@@ -253,7 +541,7 @@ impl Renderer for VgerRenderer {
                     }
                 }
 
-                if let Some(paint) = self.brush_to_paint(glyph_run.color) {
+                if let Some(paint) = self.brush_to_paint(glyph_run.color, Rect::ZERO) {
                     let glyph_x = x * self.scale as f32;
                     let (new_x, subpx_x) = SubpixelBin::new(glyph_x);
                     let glyph_x = new_x as f32;
@@ -291,55 +579,243 @@ impl Renderer for VgerRenderer {
         rect: Rect,
         brush: Option<impl Into<BrushRef<'b>>>,
     ) {
-        let transform = self.transform.as_coeffs();
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.draw_svg(svg, rect, brush);
+            return;
+        }
+        let width = (rect.width() * self.scale).round() as u32;
+        let height = (rect.height() * self.scale).round() as u32;
+        let width = width.max(1);
+        let height = height.max(1);
+        let origin = rect.origin();
+        let x = ((origin.x + self.offset.x) * self.scale).round() as f32;
+        let y = ((origin.y + self.offset.y) * self.scale).round() as f32;
+
+        let render = || {
+            let mut img = tiny_skia::Pixmap::new(width, height).unwrap();
+            let rtree = resvg::Tree::from_usvg(svg.tree);
+            let scale =
+                (width as f64 / rtree.size.width()).min(height as f64 / rtree.size.height()) as f32;
+            let transform = tiny_skia::Transform::from_scale(scale, scale);
+            rtree.render(transform, &mut img.as_mut());
+            img.take()
+        };
+        let paint = brush.and_then(|brush| self.brush_to_paint(brush, rect));
+        self.vger
+            .render_svg(x, y, svg.hash, width, height, render, paint);
+    }
+
+    fn draw_img(&mut self, img: floem_renderer::Img<'_>, rect: Rect) {
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.draw_img(img, rect);
+            return;
+        }
         let width = (rect.width() * self.scale).round() as u32;
         let height = (rect.height() * self.scale).round() as u32;
         let width = width.max(1);
         let height = height.max(1);
         let origin = rect.origin();
-        let x = ((origin.x + transform[4]) * self.scale).round() as f32;
-        let y = ((origin.y + transform[5]) * self.scale).round() as f32;
+        let x = ((origin.x + self.offset.x) * self.scale).round() as f32;
+        let y = ((origin.y + self.offset.y) * self.scale).round() as f32;
 
-        let paint = brush.and_then(|brush| self.brush_to_paint(brush));
+        // images are cached by their hash and size, the same as svgs
         self.vger.render_svg(
             x,
             y,
-            svg.hash,
+            img.hash,
             width,
             height,
             || {
-                let mut img = tiny_skia::Pixmap::new(width, height).unwrap();
-                let rtree = resvg::Tree::from_usvg(svg.tree);
-                let scale = (width as f64 / rtree.size.width())
-                    .min(height as f64 / rtree.size.height()) as f32;
-                let transform = tiny_skia::Transform::from_scale(scale, scale);
-                rtree.render(transform, &mut img.as_mut());
-                img.take()
+                img.to_pixmap(width, height)
+                    .map(|pixmap| pixmap.take())
+                    .unwrap_or_else(|| vec![0; (width * height * 4) as usize])
             },
-            paint,
+            None,
+        );
+    }
+
+    fn draw_box_shadow(
+        &mut self,
+        rect: Rect,
+        radii: RoundedRectRadii,
+        color: Color,
+        blur_radius: f64,
+    ) {
+        if let Some(layer) = &mut self.offscreen {
+            layer
+                .renderer
+                .draw_box_shadow(rect, radii, color, blur_radius);
+            return;
+        }
+        if blur_radius <= 0.0 {
+            self.fill(&rect.to_rounded_rect(radii), color);
+            return;
+        }
+        let width = rect.width() * self.scale;
+        let height = rect.height() * self.scale;
+        let blur_radius = blur_radius * self.scale;
+        let radii = RoundedRectRadii::new(
+            radii.top_left * self.scale,
+            radii.top_right * self.scale,
+            radii.bottom_right * self.scale,
+            radii.bottom_left * self.scale,
+        );
+        let extent = floem_renderer::shadow_extent(blur_radius);
+        let origin = self.vger_point(rect.origin() - Vec2::new(extent, extent) / self.scale);
+
+        let mut hasher = DefaultHasher::new();
+        for value in [
+            width,
+            height,
+            radii.top_left,
+            radii.top_right,
+            radii.bottom_right,
+            radii.bottom_left,
+            blur_radius,
+        ] {
+            value.to_bits().hash(&mut hasher);
+        }
+        (color.r, color.g, color.b, color.a).hash(&mut hasher);
+        let hash = hasher.finish().to_le_bytes();
+
+        let pixmap_width = (width + extent * 2.0).ceil().max(1.0) as u32;
+        let pixmap_height = (height + extent * 2.0).ceil().max(1.0) as u32;
+        // shadows are cached by their hash and size, the same as svgs
+        self.vger.render_svg(
+            origin.x,
+            origin.y,
+            &hash,
+            pixmap_width,
+            pixmap_height,
+            || {
+                floem_renderer::box_shadow_pixmap(width, height, radii, color, blur_radius)
+                    .map(|pixmap| pixmap.take())
+                    .unwrap_or_else(|| vec![0; (pixmap_width * pixmap_height * 4) as usize])
+            },
+            None,
         );
     }
 
     fn transform(&mut self, transform: Affine) {
         self.transform = transform;
+        if let Some(layer) = &mut self.offscreen {
+            layer.transform(transform);
+        }
+        if self.has_vger_transform {
+            self.vger.restore();
+            self.has_vger_transform = false;
+        }
+        let [a, b, c, d, e, f] = transform.as_coeffs();
+        if a == 1.0 && b == 0.0 && c == 0.0 && d == 1.0 {
+            self.offset = Vec2::new(e, f);
+            return;
+        }
+
+        // vger rotates and scales around the device origin, so the translation
+        // goes first and the points are given without it
+        self.offset = Vec2::ZERO;
+        let scale_x = a.hypot(b);
+        let scale_y = (a * d - b * c) / scale_x;
+        self.vger.save();
+        self.vger.translate(vger::defs::LocalVector::new(
+            (e * self.scale) as f32,
+            (f * self.scale) as f32,
+        ));
+        self.vger.rotate(b.atan2(a) as f32);
+        self.vger
+            .scale(vger::defs::LocalVector::new(scale_x as f32, scale_y as f32));
+        self.has_vger_transform = true;
+    }
+
+    fn push_layer(&mut self, opacity: f32) {
+        match &mut self.offscreen {
+            Some(layer) => {
+                layer.renderer.push_layer(opacity);
+                layer.layers += 1;
+            }
+            None if opacity < 1.0 => {
+                // the group is drawn offscreen and then composited at its
+                // opacity, so shapes in it don't show through each other
+                let window = Rect::new(
+                    0.0,
+                    0.0,
+                    self.config.width as f64 / self.scale,
+                    self.config.height as f64 / self.scale,
+                );
+                let rect = self.clip.unwrap_or(window);
+                if let Some(rect) = self.device_rect(rect) {
+                    self.offscreen =
+                        Offscreen::group(rect, self.scale, self.transform, opacity.max(0.0));
+                }
+            }
+            // an opaque group needs no layer of its own
+            None => {}
+        }
+    }
+
+    fn pop_layer(&mut self) {
+        if let Some(layer) = &mut self.offscreen {
+            if layer.layers > 0 {
+                layer.renderer.pop_layer();
+                layer.layers -= 1;
+            } else if layer.kind == OffscreenKind::Group {
+                if let Some(layer) = self.offscreen.take() {
+                    self.draw_offscreen(layer);
+                }
+            }
+        }
     }
 
     fn set_z_index(&mut self, z_index: i32) {
+        // the image of an offscreen layer is drawn at the z-index it was started at
+        if let Some(layer) = &mut self.offscreen {
+            layer.renderer.set_z_index(z_index);
+            layer.z_index = Some(z_index);
+            return;
+        }
         self.vger.set_z_index(z_index);
     }
 
-    fn clip(&mut self, shape: &impl Shape) {
-        let rect = shape.bounding_box();
-        self.vger.scissor(self.vger_rect(rect));
+    fn push_clip(&mut self, shape: &impl Shape) {
+        let rect = self.transform.transform_rect_bbox(shape.bounding_box());
+        let rect = match self.clip {
+            Some(clip) => clip.intersect(rect),
+            None => rect,
+        };
+        self.saved_clips.push(self.clip.replace(rect));
+        self.apply_clip();
+        match &mut self.offscreen {
+            Some(layer) => {
+                layer.renderer.push_clip(shape);
+                layer.clips += 1;
+            }
+            None => self.push_clip_offscreen(shape, rect),
+        }
+    }
 
-        let transform = self.transform.as_coeffs();
-        let offset = Vec2::new(transform[4], transform[5]);
-        self.clip = Some(rect + offset);
+    fn push_unclipped(&mut self) {
+        self.saved_clips.push(self.clip.take());
+        self.apply_clip();
+        if let Some(layer) = &mut self.offscreen {
+            // the layer doesn't reach outside the clip it's for
+            layer.renderer.push_unclipped();
+            layer.clips += 1;
+        }
     }
 
-    fn clear_clip(&mut self) {
-        self.vger.reset_scissor();
-        self.clip = None;
+    fn pop_clip(&mut self) {
+        self.clip = self.saved_clips.pop().flatten();
+        self.apply_clip();
+        if let Some(layer) = &mut self.offscreen {
+            if layer.clips > 0 {
+                layer.renderer.pop_clip();
+                layer.clips -= 1;
+            } else if layer.kind == OffscreenKind::Clip {
+                if let Some(layer) = self.offscreen.take() {
+                    self.draw_offscreen(layer);
+                }
+            }
+        }
     }
 
     fn finish(&mut self) {
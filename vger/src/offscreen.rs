@@ -0,0 +1,105 @@
+use floem_renderer::Renderer;
+use floem_tiny_skia::TinySkiaRenderer;
+use peniko::{
+    kurbo::{Affine, Rect, Shape},
+    Color,
+};
+
+/// What the offscreen layer was started for, which decides what ends it
+#[derive(PartialEq, Eq)]
+pub(crate) enum OffscreenKind {
+    /// A clip vger's rect scissor can't do, like a rounded or rotated rect,
+    /// ended by popping the clip
+    Clip,
+    /// A group that's faded as a whole, ended by popping the layer
+    Group,
+}
+
+/// Contents vger can't draw by itself, drawn with tiny-skia instead. The image
+/// of them is drawn with vger when the clip or layer they're for is popped.
+pub(crate) struct Offscreen {
+    pub(crate) renderer: TinySkiaRenderer,
+    pub(crate) kind: OffscreenKind,
+    /// The part of the window the layer covers, in device pixels
+    pub(crate) rect: Rect,
+    scale: f64,
+    /// The opacity the image of the layer is drawn with
+    pub(crate) opacity: f32,
+    /// How many clips are pushed within the layer
+    pub(crate) clips: usize,
+    /// How many layers are pushed within the layer
+    pub(crate) layers: usize,
+    /// The z-index set last within the layer
+    pub(crate) z_index: Option<i32>,
+}
+
+impl Offscreen {
+    /// A layer over `rect`, which is in device pixels, clipped to `shape`
+    /// under `transform`
+    pub(crate) fn clip(
+        rect: Rect,
+        scale: f64,
+        transform: Affine,
+        shape: &impl Shape,
+    ) -> Option<Self> {
+        let mut layer = Self::new(rect, scale, transform, OffscreenKind::Clip, 1.0)?;
+        layer.renderer.push_clip(shape);
+        Some(layer)
+    }
+
+    /// A layer over `rect`, which is in device pixels, that's drawn with `opacity`
+    pub(crate) fn group(rect: Rect, scale: f64, transform: Affine, opacity: f32) -> Option<Self> {
+        Self::new(rect, scale, transform, OffscreenKind::Group, opacity)
+    }
+
+    fn new(
+        rect: Rect,
+        scale: f64,
+        transform: Affine,
+        kind: OffscreenKind,
+        opacity: f32,
+    ) -> Option<Self> {
+        let mut renderer =
+            TinySkiaRenderer::new(rect.width() as u32, rect.height() as u32, scale).ok()?;
+        renderer.set_background(Color::TRANSPARENT);
+        renderer.begin();
+        let mut layer = Self {
+            renderer,
+            kind,
+            rect,
+            scale,
+            opacity,
+            clips: 0,
+            layers: 0,
+            z_index: None,
+        };
+        layer.transform(transform);
+        Some(layer)
+    }
+
+    /// Sets the transform of what's drawn next, which is in window coordinates
+    pub(crate) fn transform(&mut self, transform: Affine) {
+        let offset = self.rect.origin().to_vec2() / self.scale;
+        self.renderer
+            .transform(Affine::translate(-offset) * transform);
+    }
+
+    /// The pixels of the layer in RGBA with straight alpha, row by row, or
+    /// none if nothing in it was visible
+    pub(crate) fn finish(mut self) -> Option<Vec<u8>> {
+        self.renderer.finish();
+        let pixels = self.renderer.pixmap().pixels();
+        if pixels.iter().all(|pixel| pixel.alpha() == 0) {
+            return None;
+        }
+        Some(
+            pixels
+                .iter()
+                .flat_map(|pixel| {
+                    let color = pixel.demultiply();
+                    [color.red(), color.green(), color.blue(), color.alpha()]
+                })
+                .collect(),
+        )
+    }
+}
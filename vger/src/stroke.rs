@@ -0,0 +1,414 @@
+use std::f64::consts::PI;
+
+use peniko::{
+    kurbo::{
+        Arc, BezPath, CubicBez, Line, ParamCurve, ParamCurveArclen, PathEl, PathSeg, Point,
+        QuadBez, Vec2,
+    },
+    Cap, Join, Stroke,
+};
+
+/// How close the arc length of dashes has to be to the real one
+const DASH_ACCURACY: f64 = 1e-3;
+
+/// Splits `path` into the dashes of `stroke`, each one an open subpath. The
+/// dash pattern starts over at every subpath.
+pub(crate) fn dash(path: impl IntoIterator<Item = PathEl>, stroke: &Stroke) -> BezPath {
+    let mut pattern: Vec<f64> = stroke
+        .dash_pattern
+        .iter()
+        .map(|dash| *dash as f64)
+        .collect();
+    // an odd pattern repeats to make pairs of dashes and gaps
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+    let total: f64 = pattern.iter().sum();
+    let path: BezPath = path.into_iter().collect();
+    if pattern.iter().any(|dash| *dash < 0.0) || total <= 0.0 {
+        return path;
+    }
+
+    let mut dashes = BezPath::new();
+    let mut subpath = Vec::new();
+    let mut start = Point::ZERO;
+    let mut current = Point::ZERO;
+    let flush = |subpath: &mut Vec<PathSeg>, dashes: &mut BezPath| {
+        dash_subpath(subpath, &pattern, total, stroke.dash_offset as f64, dashes);
+        subpath.clear();
+    };
+    for el in path.elements() {
+        let seg = match *el {
+            PathEl::MoveTo(p) => {
+                flush(&mut subpath, &mut dashes);
+                start = p;
+                current = p;
+                continue;
+            }
+            PathEl::LineTo(p) => PathSeg::Line(Line::new(current, p)),
+            PathEl::QuadTo(p1, p2) => PathSeg::Quad(QuadBez::new(current, p1, p2)),
+            PathEl::CurveTo(p1, p2, p3) => PathSeg::Cubic(CubicBez::new(current, p1, p2, p3)),
+            PathEl::ClosePath => PathSeg::Line(Line::new(current, start)),
+        };
+        current = seg.end();
+        subpath.push(seg);
+    }
+    flush(&mut subpath, &mut dashes);
+    dashes
+}
+
+fn dash_subpath(
+    segments: &[PathSeg],
+    pattern: &[f64],
+    total: f64,
+    offset: f64,
+    dashes: &mut BezPath,
+) {
+    // find where in the pattern the subpath starts
+    let mut index = 0;
+    let mut offset = offset.rem_euclid(total);
+    while offset > 0.0 && offset >= pattern[index] {
+        offset -= pattern[index];
+        index = (index + 1) % pattern.len();
+    }
+    let mut remaining = pattern[index] - offset;
+    let mut in_dash = false;
+
+    for seg in segments {
+        let length = seg.arclen(DASH_ACCURACY);
+        let mut pos = 0.0;
+        loop {
+            let end = (pos + remaining).min(length);
+            // zero length dashes are kept, they're dots with round or square caps
+            if index % 2 == 0 && (end > pos || remaining == 0.0) {
+                let t0 = seg.inv_arclen(pos, DASH_ACCURACY);
+                let t1 = seg.inv_arclen(end, DASH_ACCURACY);
+                let piece = seg.subsegment(t0..t1);
+                if !in_dash {
+                    dashes.move_to(piece.start());
+                    in_dash = true;
+                }
+                push_segment(dashes, piece);
+            }
+            remaining -= end - pos;
+            pos = end;
+            if remaining > 0.0 {
+                break;
+            }
+            index = (index + 1) % pattern.len();
+            remaining = pattern[index];
+            in_dash = false;
+        }
+    }
+}
+
+fn push_segment(path: &mut BezPath, seg: PathSeg) {
+    match seg {
+        PathSeg::Line(line) => path.line_to(line.p1),
+        PathSeg::Quad(quad) => path.quad_to(quad.p1, quad.p2),
+        PathSeg::Cubic(cubic) => path.curve_to(cubic.p1, cubic.p2, cubic.p3),
+    }
+}
+
+/// The outline of `path` stroked with the width, caps and joins of `stroke`,
+/// to be filled. Curves are flattened to lines first.
+///
+/// Open subpaths become a single closed shape around them, closed ones become
+/// the rings on either side, which run in opposite directions and leave a hole
+/// with either fill rule.
+pub(crate) fn outline(path: &BezPath, stroke: &Stroke, tolerance: f64) -> BezPath {
+    let mut polylines: Vec<(Vec<Point>, bool)> = Vec::new();
+    path.flatten(tolerance, |el| match el {
+        PathEl::MoveTo(p) => polylines.push((vec![p], false)),
+        PathEl::LineTo(p) => {
+            if let Some((points, _)) = polylines.last_mut() {
+                if points.last() != Some(&p) {
+                    points.push(p);
+                }
+            }
+        }
+        PathEl::ClosePath => {
+            if let Some((points, closed)) = polylines.last_mut() {
+                if points.len() > 1 && points.first() == points.last() {
+                    points.pop();
+                }
+                *closed = true;
+            }
+        }
+        // flattening only makes lines
+        PathEl::QuadTo(..) | PathEl::CurveTo(..) => {}
+    });
+
+    let half = stroke.width as f64 / 2.0;
+    let mut outline = BezPath::new();
+    for (mut points, closed) in polylines {
+        if points.len() == 1 {
+            // a zero length subpath, like the dashes of a dotted line
+            dot(&mut outline, points[0], half, stroke.start_cap, tolerance);
+        } else if closed && points.len() > 2 {
+            side(&mut outline, &points, true, true, stroke, half, tolerance);
+            outline.close_path();
+            points.reverse();
+            side(&mut outline, &points, true, true, stroke, half, tolerance);
+            outline.close_path();
+        } else {
+            side(&mut outline, &points, false, true, stroke, half, tolerance);
+            let end = points.len() - 1;
+            cap(
+                &mut outline,
+                points[end],
+                direction(points[end - 1], points[end]),
+                half,
+                stroke.end_cap,
+                tolerance,
+            );
+            points.reverse();
+            side(&mut outline, &points, false, false, stroke, half, tolerance);
+            cap(
+                &mut outline,
+                points[end],
+                direction(points[end - 1], points[end]),
+                half,
+                stroke.start_cap,
+                tolerance,
+            );
+            outline.close_path();
+        }
+    }
+    outline
+}
+
+/// Walks along the left side of `points`, half the width away from them, and
+/// joins the lines at every point in between. The walk starts a new subpath
+/// if `start` is set, or goes on from the end of the outline otherwise.
+fn side(
+    outline: &mut BezPath,
+    points: &[Point],
+    closed: bool,
+    start: bool,
+    stroke: &Stroke,
+    half: f64,
+    tolerance: f64,
+) {
+    let count = points.len();
+    let mut first = start;
+    let mut push = |outline: &mut BezPath, point: Point| {
+        if first {
+            outline.move_to(point);
+            first = false;
+        } else {
+            outline.line_to(point);
+        }
+    };
+    if !closed {
+        push(outline, points[0] + left(points[0], points[1]) * half);
+    }
+    let joins = if closed { 0..count } else { 1..count - 1 };
+    for i in joins {
+        let prev = points[(i + count - 1) % count];
+        let point = points[i];
+        let next = points[(i + 1) % count];
+        let (d0, d1) = (direction(prev, point), direction(point, next));
+        let (n0, n1) = (left(prev, point), left(point, next));
+        let a = point + n0 * half;
+        let b = point + n1 * half;
+        let sum = n0 + n1;
+        if d0.cross(d1).abs() < 1e-9 && d0.dot(d1) > 0.0 {
+            // no turn
+            push(outline, a);
+            continue;
+        }
+        // the ratio of the length of a miter to half the width
+        let miter = 2.0 / sum.hypot();
+        let miter_point = point + sum * (2.0 * half / sum.hypot2());
+        if d1.dot(n0) > 0.0 {
+            // turning to this side, the lines meet at the inner miter, unless
+            // it's further away than the lines are long
+            let shortest = (point - prev).hypot().min((next - point).hypot());
+            if sum.hypot2() > 1e-9 && miter * half <= shortest {
+                push(outline, miter_point);
+            } else {
+                push(outline, a);
+                push(outline, b);
+            }
+            continue;
+        }
+        push(outline, a);
+        match stroke.join {
+            Join::Miter if sum.hypot2() > 1e-9 && miter <= stroke.miter_limit as f64 => {
+                push(outline, miter_point);
+            }
+            Join::Round => {
+                let sweep = n0.cross(n1).atan2(n0.dot(n1));
+                arc(outline, point, half, n0, sweep, tolerance);
+            }
+            Join::Miter | Join::Bevel => {}
+        }
+        push(outline, b);
+    }
+    if !closed {
+        push(
+            outline,
+            points[count - 1] + left(points[count - 2], points[count - 1]) * half,
+        );
+    }
+}
+
+/// Goes around the end at `point`, from the left side to the right side of a
+/// line going in `direction`
+fn cap(outline: &mut BezPath, point: Point, direction: Vec2, half: f64, cap: Cap, tolerance: f64) {
+    let normal = Vec2::new(-direction.y, direction.x);
+    match cap {
+        Cap::Butt => {}
+        Cap::Square => {
+            outline.line_to(point + (normal + direction) * half);
+            outline.line_to(point + (direction - normal) * half);
+        }
+        Cap::Round => arc(outline, point, half, normal, -PI, tolerance),
+    }
+    outline.line_to(point - normal * half);
+}
+
+fn dot(outline: &mut BezPath, point: Point, half: f64, cap: Cap, tolerance: f64) {
+    match cap {
+        Cap::Butt => {}
+        Cap::Square => {
+            outline.move_to(point + Vec2::new(-half, -half));
+            outline.line_to(point + Vec2::new(half, -half));
+            outline.line_to(point + Vec2::new(half, half));
+            outline.line_to(point + Vec2::new(-half, half));
+            outline.close_path();
+        }
+        Cap::Round => {
+            outline.move_to(point + Vec2::new(half, 0.0));
+            arc(
+                outline,
+                point,
+                half,
+                Vec2::new(1.0, 0.0),
+                2.0 * PI,
+                tolerance,
+            );
+            outline.close_path();
+        }
+    }
+}
+
+/// Adds the arc around `center` that starts in the direction of `from`
+fn arc(outline: &mut BezPath, center: Point, radius: f64, from: Vec2, sweep: f64, tolerance: f64) {
+    let arc = Arc {
+        center,
+        radii: Vec2::new(radius, radius),
+        start_angle: from.atan2(),
+        sweep_angle: sweep,
+        x_rotation: 0.0,
+    };
+    outline.extend(arc.append_iter(tolerance));
+}
+
+fn direction(from: Point, to: Point) -> Vec2 {
+    (to - from).normalize()
+}
+
+/// The unit normal to the left of the line from `from` to `to`
+fn left(from: Point, to: Point) -> Vec2 {
+    let direction = direction(from, to);
+    Vec2::new(-direction.y, direction.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use peniko::{
+        kurbo::{BezPath, Line, PathEl, Rect, Shape},
+        Cap, Join, Stroke,
+    };
+
+    use super::{dash, outline};
+
+    fn subpaths(path: &BezPath) -> Vec<Rect> {
+        let mut rects: Vec<Rect> = Vec::new();
+        for el in path.elements() {
+            match *el {
+                PathEl::MoveTo(p) => rects.push(Rect::from_points(p, p)),
+                PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => {
+                    let rect = rects.last_mut().unwrap();
+                    *rect = rect.union_pt(p);
+                }
+                PathEl::ClosePath => {}
+            }
+        }
+        rects
+    }
+
+    #[test]
+    fn dashes_start_over_on_every_subpath() {
+        let stroke = Stroke::new(1.0).with_dashes(2.0, [4.0, 4.0]);
+        let mut path = Line::new((0.0, 0.0), (20.0, 0.0)).to_path(0.1);
+        path.extend(Line::new((0.0, 10.0), (6.0, 10.0)).path_elements(0.1));
+        let dashes = subpaths(&dash(path, &stroke));
+        assert_eq!(
+            dashes,
+            vec![
+                Rect::new(0.0, 0.0, 2.0, 0.0),
+                Rect::new(6.0, 0.0, 10.0, 0.0),
+                Rect::new(14.0, 0.0, 18.0, 0.0),
+                Rect::new(0.0, 10.0, 2.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn dashes_go_around_corners() {
+        let stroke = Stroke::new(1.0).with_dashes(0.0, [15.0, 5.0]);
+        let path = Rect::new(0.0, 0.0, 10.0, 10.0).to_path(0.1);
+        let dashes = subpaths(&dash(path, &stroke));
+        assert_eq!(
+            dashes,
+            vec![
+                Rect::new(0.0, 0.0, 10.0, 5.0),
+                Rect::new(0.0, 5.0, 10.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn butt_and_square_caps() {
+        let line = Line::new((0.0, 5.0), (20.0, 5.0)).to_path(0.1);
+        let butt = outline(&line, &Stroke::new(2.0).with_caps(Cap::Butt), 0.1);
+        assert!(butt.contains((1.0, 5.5).into()));
+        assert!(!butt.contains((10.0, 6.5).into()));
+        assert!(!butt.contains((-0.5, 5.0).into()));
+
+        let square = outline(&line, &Stroke::new(2.0).with_caps(Cap::Square), 0.1);
+        assert!(square.contains((-0.5, 5.0).into()));
+        assert!(square.contains((20.5, 4.5).into()));
+        assert!(!square.contains((21.5, 5.0).into()));
+    }
+
+    #[test]
+    fn closed_paths_leave_a_hole() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0).to_path(0.1);
+        let miter = outline(&rect, &Stroke::new(2.0).with_join(Join::Miter), 0.1);
+        assert!(miter.contains((0.0, 5.0).into()));
+        assert!(miter.contains((-0.9, -0.9).into()));
+        assert!(!miter.contains((5.0, 5.0).into()));
+
+        let bevel = outline(&rect, &Stroke::new(2.0).with_join(Join::Bevel), 0.1);
+        assert!(bevel.contains((-0.4, -0.4).into()));
+        assert!(!bevel.contains((-0.9, -0.9).into()));
+    }
+
+    #[test]
+    fn zero_length_dashes_are_round_dots() {
+        let stroke = Stroke::new(2.0)
+            .with_caps(Cap::Round)
+            .with_dashes(0.0, [0.0, 5.0]);
+        let line = Line::new((0.0, 0.0), (12.0, 0.0)).to_path(0.1);
+        let dots = outline(&dash(line, &stroke), &stroke, 0.1);
+        assert!(dots.contains((0.5, 0.5).into()));
+        assert!(dots.contains((5.0, -0.9).into()));
+        assert!(dots.contains((10.5, 0.0).into()));
+        assert!(!dots.contains((2.5, 0.0).into()));
+        assert!(!dots.contains((5.8, 0.8).into()));
+    }
+}